@@ -0,0 +1,407 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+use nalgebra_glm::Vec3;
+
+use crate::material::{MaterialOverride, MaterialParam};
+use crate::ray_intersect::RayIntersect;
+use crate::scene::Scene;
+use crate::transform::Transform;
+
+/// Curva de interpolación entre una clave y la siguiente.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Easing {
+    #[default]
+    Linear,
+    // Mantiene el valor hasta la siguiente clave.
+    Step,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::Step => 0.0,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+pub trait Lerp: Copy {
+    fn lerp(&self, other: &Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(&self, other: &f32, t: f32) -> f32 {
+        self + (other - self) * t
+    }
+}
+
+impl Lerp for Vec3 {
+    fn lerp(&self, other: &Vec3, t: f32) -> Vec3 {
+        self + (other - self) * t
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Keyframe<T> {
+    pub time: f32,
+    pub value: T,
+    // Curva usada desde esta clave hasta la siguiente.
+    pub easing: Easing,
+}
+
+impl<T> Keyframe<T> {
+    pub fn new(time: f32, value: T) -> Self {
+        Keyframe { time, value, easing: Easing::Linear }
+    }
+
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+}
+
+/// Claves ordenadas por tiempo. Fuera del rango se mantiene el extremo, o
+/// se repite si la pista es cíclica.
+#[derive(Debug, Clone)]
+pub struct Track<T> {
+    keys: Vec<Keyframe<T>>,
+    looping: bool,
+}
+
+impl<T: Lerp> Track<T> {
+    pub fn new(mut keys: Vec<Keyframe<T>>) -> Self {
+        keys.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Track { keys, looping: false }
+    }
+
+    /// Repite la pista con periodo igual al tiempo de la última clave.
+    pub fn looping(mut self) -> Self {
+        self.looping = true;
+        self
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.keys.last().map_or(0.0, |k| k.time)
+    }
+
+    pub fn sample(&self, time: f32) -> Option<T> {
+        let first = self.keys.first()?;
+        let duration = self.duration();
+        let time = if self.looping && duration > 0.0 { time.rem_euclid(duration) } else { time };
+
+        if time <= first.time {
+            return Some(first.value);
+        }
+        let next = match self.keys.iter().position(|k| k.time > time) {
+            Some(next) => next,
+            None => return self.keys.last().map(|k| k.value),
+        };
+        let (a, b) = (&self.keys[next - 1], &self.keys[next]);
+        let t = (time - a.time) / (b.time - a.time);
+        Some(a.value.lerp(&b.value, a.easing.apply(t)))
+    }
+}
+
+/// Reloj de la escena: se puede pausar, mover a mano y avanzar paso a paso.
+#[derive(Debug, Clone)]
+pub struct Clock {
+    time: f32,
+    pub speed: f32,
+    paused: bool,
+    // Saltos pedidos con `scrub` o `step`, aplicados en el próximo `tick`.
+    pending: f32,
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Clock { time: 0.0, speed: 1.0, paused: false, pending: 0.0 }
+    }
+}
+
+impl Clock {
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    /// Desplaza el tiempo `delta` segundos (también hacia atrás).
+    pub fn scrub(&mut self, delta: f32) {
+        self.pending += delta;
+    }
+
+    /// Avanza un paso fijo; útil con el reloj en pausa.
+    pub fn step(&mut self, dt: f32) {
+        self.pending += dt;
+    }
+
+    pub fn set_time(&mut self, time: f32) {
+        self.pending = 0.0;
+        self.time = time.max(0.0);
+    }
+
+    /// Avanza `dt` segundos reales y devuelve cuánto cambió el tiempo.
+    pub fn tick(&mut self, dt: f32) -> f32 {
+        let running = if self.paused { 0.0 } else { dt * self.speed };
+        let previous = self.time;
+        self.time = (self.time + running + self.pending).max(0.0);
+        self.pending = 0.0;
+        self.time - previous
+    }
+}
+
+#[derive(Clone)]
+pub enum NodeProperty {
+    // Forman `SceneNode::animation`, que se aplica tras la transformación
+    // del nodo: T * R * S.
+    Translation(Track<Vec3>),
+    Rotation { axis: Vec3, angle: Track<f32> },
+    Scale(Track<Vec3>),
+    Material(MaterialParam, Track<f32>),
+    // Cambia el objeto del nodo por uno de `frames`, p. ej. uno por textura.
+    Frames { frames: Vec<Rc<dyn RayIntersect>>, frame: Track<f32> },
+}
+
+#[derive(Debug, Clone)]
+pub enum LightProperty {
+    Position(Track<Vec3>),
+    // Componentes entre 0 y 255.
+    Color(Track<Vec3>),
+    Intensity(Track<f32>),
+}
+
+#[derive(Clone)]
+enum Channel {
+    Node { name: String, property: NodeProperty },
+    Light { index: usize, property: LightProperty },
+}
+
+/// Conjunto de pistas aplicadas sobre la escena en un instante dado.
+#[derive(Clone, Default)]
+pub struct Animator {
+    channels: Vec<Channel>,
+}
+
+impl Animator {
+    pub fn new() -> Self {
+        Animator::default()
+    }
+
+    pub fn with_node(mut self, name: &str, property: NodeProperty) -> Self {
+        self.add_node(name, property);
+        self
+    }
+
+    pub fn with_light(mut self, index: usize, property: LightProperty) -> Self {
+        self.add_light(index, property);
+        self
+    }
+
+    pub fn add_node(&mut self, name: &str, property: NodeProperty) {
+        self.channels.push(Channel::Node { name: name.to_string(), property });
+    }
+
+    pub fn add_light(&mut self, index: usize, property: LightProperty) {
+        self.channels.push(Channel::Light { index, property });
+    }
+
+    /// Evalúa todas las pistas en `time` y actualiza nodos y luces. Los
+    /// canales de nodos o luces inexistentes se ignoran.
+    pub fn apply(&self, scene: &mut Scene, time: f32) {
+        // Traslación, rotación y escala animadas por nodo
+        let mut poses: HashMap<&str, (Transform, Transform, Transform)> = HashMap::new();
+        let mut changed: Vec<&str> = Vec::new();
+
+        for channel in &self.channels {
+            match channel {
+                Channel::Node { name, property } => {
+                    let node = match scene.root.find_mut(name) {
+                        Some(node) => node,
+                        None => continue,
+                    };
+                    let identity = Transform::identity();
+                    match property {
+                        NodeProperty::Translation(track) => {
+                            if let Some(offset) = track.sample(time) {
+                                poses.entry(name).or_insert((identity, identity, identity)).0 = Transform::translation(offset);
+                            }
+                        }
+                        NodeProperty::Rotation { axis, angle } => {
+                            if let Some(angle) = angle.sample(time) {
+                                poses.entry(name).or_insert((identity, identity, identity)).1 = Transform::rotation(angle, *axis);
+                            }
+                        }
+                        NodeProperty::Scale(track) => {
+                            if let Some(factors) = track.sample(time) {
+                                poses.entry(name).or_insert((identity, identity, identity)).2 = Transform::scaling(factors);
+                            }
+                        }
+                        NodeProperty::Material(param, track) => {
+                            if let Some(value) = track.sample(time) {
+                                let previous = node.material.clone();
+                                node.material.get_or_insert_with(MaterialOverride::default).set(*param, value);
+                                if node.material != previous {
+                                    mark_changed(&mut changed, name);
+                                }
+                            }
+                        }
+                        NodeProperty::Frames { frames, frame } => {
+                            if let (Some(value), false) = (frame.sample(time), frames.is_empty()) {
+                                let index = (value.max(0.0) as usize) % frames.len();
+                                if !node.object.as_ref().is_some_and(|o| Rc::ptr_eq(o, &frames[index])) {
+                                    node.object = Some(frames[index].clone());
+                                    mark_changed(&mut changed, name);
+                                }
+                            }
+                        }
+                    }
+                }
+                Channel::Light { index, property } => {
+                    let light = match scene.lights.get_mut(*index) {
+                        Some(light) => light,
+                        None => continue,
+                    };
+                    match property {
+                        LightProperty::Position(track) => {
+                            if let Some(position) = track.sample(time) {
+                                light.position = position;
+                            }
+                        }
+                        LightProperty::Color(track) => {
+                            if let Some(color) = track.sample(time) {
+                                let channel = |c: f32| c.clamp(0.0, 255.0) as u8;
+                                light.color = [channel(color.x), channel(color.y), channel(color.z)];
+                            }
+                        }
+                        LightProperty::Intensity(track) => {
+                            if let Some(intensity) = track.sample(time) {
+                                light.intensity = intensity.max(0.0);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        for (name, (translation, rotation, scale)) in poses {
+            let animation = translation * rotation * scale;
+            if let Some(node) = scene.root.find_mut(name) {
+                if node.animation != animation {
+                    node.animation = animation;
+                    mark_changed(&mut changed, name);
+                }
+            }
+        }
+
+        // Solo se vuelven a aplanar los nodos que cambiaron; con el reloj en
+        // pausa la escena queda intacta
+        for name in changed {
+            scene.rebuild_node(name);
+        }
+    }
+}
+
+fn mark_changed<'a>(changed: &mut Vec<&'a str>, name: &'a str) {
+    if !changed.contains(&name) {
+        changed.push(name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-5
+    }
+
+    #[test]
+    fn keyframes_interpolate_with_their_easing() {
+        // Se ordenan al construir la pista
+        let track = Track::new(vec![
+            Keyframe::new(2.0, 10.0),
+            Keyframe::new(0.0, 0.0).with_easing(Easing::EaseIn),
+            Keyframe::new(1.0, 4.0),
+        ]);
+        assert_eq!(track.duration(), 2.0);
+        assert!(close(track.sample(0.5).unwrap(), 1.0));
+        assert!(close(track.sample(1.5).unwrap(), 7.0));
+        // Fuera del rango se mantienen los extremos
+        assert_eq!(track.sample(-1.0), Some(0.0));
+        assert_eq!(track.sample(5.0), Some(10.0));
+
+        let positions = Track::new(vec![Keyframe::new(0.0, Vec3::zeros()), Keyframe::new(4.0, Vec3::new(4.0, -2.0, 8.0))]);
+        assert_eq!(positions.sample(1.0), Some(Vec3::new(1.0, -0.5, 2.0)));
+        assert_eq!(Track::<f32>::new(Vec::new()).sample(1.0), None);
+    }
+
+    #[test]
+    fn looping_tracks_wrap_in_both_directions() {
+        let track = Track::new(vec![Keyframe::new(0.0, 0.0), Keyframe::new(1.0, 1.0), Keyframe::new(2.0, 0.0)]).looping();
+        assert!(close(track.sample(0.5).unwrap(), 0.5));
+        assert!(close(track.sample(4.5).unwrap(), 0.5));
+        assert!(close(track.sample(3.25).unwrap(), 0.75));
+        // rem_euclid: los tiempos negativos siguen en [0, duración)
+        assert!(close(track.sample(-0.5).unwrap(), 0.5));
+        assert!(close(track.sample(-1.75).unwrap(), 0.25));
+    }
+
+    #[test]
+    fn step_holds_until_the_next_key() {
+        let track = Track::new(vec![
+            Keyframe::new(0.0, 1.0).with_easing(Easing::Step),
+            Keyframe::new(1.0, 5.0).with_easing(Easing::Step),
+            Keyframe::new(2.0, 9.0),
+        ]);
+        assert_eq!(track.sample(0.99), Some(1.0));
+        assert_eq!(track.sample(1.0), Some(5.0));
+        assert_eq!(track.sample(1.5), Some(5.0));
+        assert_eq!(track.sample(2.0), Some(9.0));
+    }
+
+    #[test]
+    fn clock_pause_scrub_and_step() {
+        let mut clock = Clock::default();
+        assert_eq!(clock.tick(0.5), 0.5);
+
+        clock.toggle_pause();
+        assert!(clock.is_paused());
+        assert_eq!(clock.tick(1.0), 0.0);
+        clock.step(0.25);
+        assert_eq!(clock.tick(1.0), 0.25);
+        assert_eq!(clock.time(), 0.75);
+
+        // Hacia atrás, sin bajar de cero
+        clock.scrub(-0.5);
+        assert_eq!(clock.tick(0.0), -0.5);
+        clock.scrub(-2.0);
+        assert_eq!(clock.tick(0.0), -0.25);
+        assert_eq!(clock.time(), 0.0);
+
+        clock.set_paused(false);
+        clock.speed = 2.0;
+        clock.scrub(1.0);
+        assert_eq!(clock.tick(0.5), 2.0);
+        clock.scrub(3.0);
+        clock.set_time(0.5);
+        assert_eq!(clock.tick(0.0), 0.0);
+        assert_eq!(clock.time(), 0.5);
+    }
+}
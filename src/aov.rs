@@ -0,0 +1,150 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use image::{Rgb32FImage, RgbImage};
+use nalgebra_glm::Vec3;
+
+use crate::capture::CaptureError;
+use crate::debug_view;
+
+/// Pasadas que se pueden exportar para componer la imagen fuera del programa.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pass {
+    // Imagen final, con niebla.
+    Beauty,
+    // Distancia sobre el eje de la cámara; el cielo vale `SKY_DEPTH`.
+    Depth,
+    // Normal en mundo, de -1 a 1.
+    Normal,
+    Albedo,
+    // Luz del sol y la luna, con sus brillos.
+    Direct,
+    // Luz ambiente del cielo.
+    Indirect,
+    // 1 iluminado, 0 en sombra.
+    Shadow,
+    Reflection,
+    Refraction,
+    // Índice del objeto más uno; 0 es el cielo.
+    ObjectId,
+}
+
+/// Profundidad que se guarda donde no hay geometría.
+pub const SKY_DEPTH: f32 = 1.0e6;
+
+impl Pass {
+    pub const ALL: [Pass; 10] = [
+        Pass::Beauty,
+        Pass::Depth,
+        Pass::Normal,
+        Pass::Albedo,
+        Pass::Direct,
+        Pass::Indirect,
+        Pass::Shadow,
+        Pass::Reflection,
+        Pass::Refraction,
+        Pass::ObjectId,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Pass::Beauty => "beauty",
+            Pass::Depth => "depth",
+            Pass::Normal => "normal",
+            Pass::Albedo => "albedo",
+            Pass::Direct => "direct",
+            Pass::Indirect => "indirect",
+            Pass::Shadow => "shadow",
+            Pass::Reflection => "reflection",
+            Pass::Refraction => "refraction",
+            Pass::ObjectId => "object_id",
+        }
+    }
+
+    // Valor de la pasada donde el rayo va al cielo, salvo `Beauty`.
+    fn background(self) -> Vec3 {
+        match self {
+            Pass::Depth => Vec3::repeat(SKY_DEPTH),
+            _ => Vec3::zeros(),
+        }
+    }
+
+    // Versión de 8 bits para verla sin un programa de composición.
+    fn preview(self, value: &Vec3) -> [u8; 3] {
+        let hex = match self {
+            Pass::Depth => debug_view::depth_color(value.x),
+            Pass::Normal if *value == Vec3::zeros() => 0,
+            Pass::Normal => debug_view::normal_color(value),
+            Pass::ObjectId if value.x == 0.0 => 0,
+            Pass::ObjectId => debug_view::id_color(value.x as usize - 1),
+            _ => {
+                let channel = |v: f32| (v.clamp(0.0, 1.0) * 255.0) as u8;
+                return [channel(value.x), channel(value.y), channel(value.z)];
+            }
+        };
+        [(hex >> 16) as u8, (hex >> 8) as u8, hex as u8]
+    }
+}
+
+/// Formato de los archivos de `AovBuffers::write`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AovFormat {
+    // 8 bits por canal, recortado a [0, 1].
+    Png,
+    // Coma flotante de 32 bits sin recortar.
+    Exr,
+    Both,
+}
+
+/// Una imagen RGB en coma flotante por pasada.
+pub struct AovBuffers {
+    pub width: usize,
+    pub height: usize,
+    passes: Vec<Vec<Vec3>>,
+}
+
+impl AovBuffers {
+    pub fn new(width: usize, height: usize) -> Self {
+        let passes = Pass::ALL
+            .iter()
+            .map(|pass| vec![pass.background(); width * height])
+            .collect();
+        AovBuffers { width, height, passes }
+    }
+
+    pub fn set(&mut self, pass: Pass, x: usize, y: usize, value: Vec3) {
+        if x < self.width && y < self.height {
+            self.passes[pass as usize][y * self.width + x] = value;
+        }
+    }
+
+    pub fn get(&self, pass: Pass, x: usize, y: usize) -> Vec3 {
+        self.passes[pass as usize][y * self.width + x]
+    }
+
+    /// Escribe cada pasada como `<dir>/<pasada>.png` y/o `.exr` y devuelve
+    /// las rutas creadas.
+    pub fn write(&self, dir: &Path, format: AovFormat) -> Result<Vec<PathBuf>, CaptureError> {
+        fs::create_dir_all(dir).map_err(|source| CaptureError::Io { path: dir.to_path_buf(), source })?;
+        let (width, height) = (self.width as u32, self.height as u32);
+        let mut written = Vec::new();
+
+        for pass in Pass::ALL {
+            let pixels = &self.passes[pass as usize];
+            if format != AovFormat::Exr {
+                let path = dir.join(format!("{}.png", pass.name()));
+                let data = pixels.iter().flat_map(|p| pass.preview(p)).collect();
+                let image = RgbImage::from_raw(width, height, data).expect("pass size matches the buffers");
+                image.save(&path).map_err(|source| CaptureError::Image { path: path.clone(), source })?;
+                written.push(path);
+            }
+            if format != AovFormat::Png {
+                let path = dir.join(format!("{}.exr", pass.name()));
+                let data = pixels.iter().flat_map(|p| [p.x, p.y, p.z]).collect();
+                let image = Rgb32FImage::from_raw(width, height, data).expect("pass size matches the buffers");
+                image.save(&path).map_err(|source| CaptureError::Image { path: path.clone(), source })?;
+                written.push(path);
+            }
+        }
+        Ok(written)
+    }
+}
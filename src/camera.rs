@@ -2,12 +2,14 @@
 use nalgebra_glm::Vec3;
 use std::f32::consts::PI;
 
+#[derive(Debug, Clone)]
 pub struct Camera {
     pub eye: Vec3,
     pub center: Vec3,
     pub up: Vec3,
     pub fov: f32,
-
+    // Desplazamiento subpíxel que `render` suma a los rayos primarios.
+    pub jitter: (f32, f32),
 }
 
 impl Camera {
@@ -16,7 +18,8 @@ impl Camera {
             eye,
             center,
             up,
-            fov: 90.0,
+            fov: 60.0,
+            jitter: (0.0, 0.0),
         }
     }
     pub fn zoom(&mut self, delta: f32) {
@@ -40,6 +43,38 @@ impl Camera {
         return rotated.normalize();
     }
 
+    /// Dirección en mundo del rayo que pasa por el píxel (x, y) de una imagen
+    /// de `width` x `height`.
+    pub fn primary_ray(&self, x: f32, y: f32, width: f32, height: f32) -> Vec3 {
+        let perspective_scale = (self.fov.to_radians() * 0.5).tan();
+        let screen_x = (2.0 * x / width) - 1.0;
+        let screen_y = -(2.0 * y / height) + 1.0;
+        let direction = Vec3::new(
+            screen_x * (width / height) * perspective_scale,
+            screen_y * perspective_scale,
+            -1.0,
+        );
+        self.base_change(&direction.normalize())
+    }
+
+    /// Inverso de `primary_ray`: píxel donde se ve `point`, o `None` si
+    /// queda detrás de la cámara.
+    pub fn project(&self, point: &Vec3, width: f32, height: f32) -> Option<(f32, f32)> {
+        let forward = (self.center - self.eye).normalize();
+        let right = forward.cross(&self.up).normalize();
+        let up = right.cross(&forward).normalize();
+
+        let relative = point - self.eye;
+        let depth = relative.dot(&forward);
+        if depth <= 1e-4 {
+            return None;
+        }
+        let perspective_scale = (self.fov.to_radians() * 0.5).tan();
+        let screen_x = relative.dot(&right) / depth / (perspective_scale * width / height);
+        let screen_y = relative.dot(&up) / depth / perspective_scale;
+        Some(((screen_x + 1.0) * 0.5 * width, (1.0 - screen_y) * 0.5 * height))
+    }
+
     pub fn orbit(&mut self, delta_yaw: f32, delta_pitch: f32) {
         let radius_vector = self.eye - self.center;
         let radius = radius_vector.magnitude();
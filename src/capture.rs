@@ -0,0 +1,211 @@
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use image::{ImageError, RgbImage};
+
+use crate::framebuffer::Framebuffer;
+
+#[derive(Debug)]
+pub enum CaptureError {
+    Io { path: PathBuf, source: io::Error },
+    Image { path: PathBuf, source: ImageError },
+    // El tamaño del framebuffer cambió a mitad de una grabación en crudo.
+    SizeChanged { expected: (usize, usize), found: (usize, usize) },
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CaptureError::Io { path, source } => write!(f, "failed to write {}: {}", path.display(), source),
+            CaptureError::Image { path, source } => write!(f, "failed to save {}: {}", path.display(), source),
+            CaptureError::SizeChanged { expected, found } => write!(
+                f,
+                "frame size changed from {}x{} to {}x{} during raw recording",
+                expected.0, expected.1, found.0, found.1
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CaptureError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CaptureError::Io { source, .. } => Some(source),
+            CaptureError::Image { source, .. } => Some(source),
+            CaptureError::SizeChanged { .. } => None,
+        }
+    }
+}
+
+fn io_error(path: &Path) -> impl FnOnce(io::Error) -> CaptureError + '_ {
+    move |source| CaptureError::Io { path: path.to_path_buf(), source }
+}
+
+/// Fecha y hora UTC actual como "AAAAMMDD-HHMMSS".
+pub fn timestamp() -> String {
+    format_timestamp(SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()))
+}
+
+// Segundos desde 1970 en UTC como "AAAAMMDD-HHMMSS".
+fn format_timestamp(seconds: u64) -> String {
+    let days = (seconds / 86_400) as i64;
+    let time = seconds % 86_400;
+
+    // Días desde 1970 a fecha civil (algoritmo de Howard Hinnant)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}{:02}{:02}-{:02}{:02}{:02}", year, month, day, time / 3600, time / 60 % 60, time % 60)
+}
+
+fn to_rgb(framebuffer: &Framebuffer) -> Vec<u8> {
+    framebuffer
+        .buffer
+        .iter()
+        .flat_map(|p| [(p >> 16) as u8, (p >> 8) as u8, *p as u8])
+        .collect()
+}
+
+/// Guarda el framebuffer como PNG.
+pub fn save_png(framebuffer: &Framebuffer, path: &Path) -> Result<(), CaptureError> {
+    let image = RgbImage::from_raw(framebuffer.width as u32, framebuffer.height as u32, to_rgb(framebuffer))
+        .expect("framebuffer size matches its buffer");
+    image
+        .save(path)
+        .map_err(|source| CaptureError::Image { path: path.to_path_buf(), source })
+}
+
+/// Guarda una captura en `dir` con la fecha en el nombre y devuelve la ruta.
+pub fn screenshot(framebuffer: &Framebuffer, dir: &Path) -> Result<PathBuf, CaptureError> {
+    fs::create_dir_all(dir).map_err(io_error(dir))?;
+    let stamp = timestamp();
+    let mut path = dir.join(format!("screenshot-{}.png", stamp));
+    // Varias capturas en el mismo segundo no se pisan
+    let mut n = 1;
+    while path.exists() {
+        path = dir.join(format!("screenshot-{}-{}.png", stamp, n));
+        n += 1;
+    }
+    save_png(framebuffer, &path)?;
+    Ok(path)
+}
+
+/// Cómo se guardan los cuadros de una grabación.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordFormat {
+    // Un PNG numerado por cuadro.
+    Png,
+    // Todos los cuadros RGB24 seguidos en `frames.rgb`, para pasarlos a un
+    // codificador, p. ej. `ffmpeg -f rawvideo -pix_fmt rgb24 -s WxH -i frames.rgb`.
+    Raw,
+}
+
+/// Graba cada cuadro en una carpeta junto con `frames.csv`, que anota el
+/// tiempo de simulación y la hora del día de cada uno.
+pub struct Recorder {
+    dir: PathBuf,
+    frame: u64,
+    size: (usize, usize),
+    times: BufWriter<File>,
+    raw: Option<BufWriter<File>>,
+}
+
+impl Recorder {
+    /// Empieza una grabación en una carpeta nueva dentro de `parent`.
+    pub fn start(parent: &Path, format: RecordFormat) -> Result<Self, CaptureError> {
+        let dir = parent.join(format!("recording-{}", timestamp()));
+        fs::create_dir_all(&dir).map_err(io_error(&dir))?;
+
+        let times_path = dir.join("frames.csv");
+        let mut times = BufWriter::new(File::create(&times_path).map_err(io_error(&times_path))?);
+        writeln!(times, "frame,sim_time,time_of_day").map_err(io_error(&times_path))?;
+
+        let raw = match format {
+            RecordFormat::Png => None,
+            RecordFormat::Raw => {
+                let raw_path = dir.join("frames.rgb");
+                Some(BufWriter::new(File::create(&raw_path).map_err(io_error(&raw_path))?))
+            }
+        };
+
+        Ok(Recorder { dir, frame: 0, size: (0, 0), times, raw })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn frame_count(&self) -> u64 {
+        self.frame
+    }
+
+    /// Añade un cuadro con su tiempo de simulación.
+    pub fn record(&mut self, framebuffer: &Framebuffer, sim_time: f32, time_of_day: f32) -> Result<(), CaptureError> {
+        let size = (framebuffer.width, framebuffer.height);
+        if self.frame == 0 {
+            self.size = size;
+        }
+
+        match &mut self.raw {
+            None => {
+                let path = self.dir.join(format!("frame-{:06}.png", self.frame));
+                save_png(framebuffer, &path)?;
+            }
+            Some(raw) => {
+                if size != self.size {
+                    return Err(CaptureError::SizeChanged { expected: self.size, found: size });
+                }
+                let path = self.dir.join("frames.rgb");
+                raw.write_all(&to_rgb(framebuffer)).map_err(io_error(&path))?;
+            }
+        }
+
+        let times_path = self.dir.join("frames.csv");
+        writeln!(self.times, "{},{:.6},{:.6}", self.frame, sim_time, time_of_day).map_err(io_error(&times_path))?;
+        self.frame += 1;
+        Ok(())
+    }
+
+    /// Termina la grabación vaciando los archivos abiertos.
+    pub fn finish(mut self) -> Result<(), CaptureError> {
+        let times_path = self.dir.join("frames.csv");
+        self.times.flush().map_err(io_error(&times_path))?;
+        if let Some(raw) = &mut self.raw {
+            let raw_path = self.dir.join("frames.rgb");
+            raw.flush().map_err(io_error(&raw_path))?;
+            // Tamaño de los cuadros, necesario para leer el archivo en crudo
+            let info_path = self.dir.join("frames.txt");
+            fs::write(&info_path, format!("{}x{} rgb24\n", self.size.0, self.size.1)).map_err(io_error(&info_path))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_known_dates() {
+        assert_eq!(format_timestamp(0), "19700101-000000");
+        assert_eq!(format_timestamp(951_782_400), "20000229-000000");
+        assert_eq!(format_timestamp(1_700_000_000), "20231114-221320");
+        assert_eq!(format_timestamp(4_102_444_799), "20991231-235959");
+    }
+
+    #[test]
+    fn timestamp_has_the_file_name_layout() {
+        let stamp = timestamp();
+        assert_eq!(stamp.len(), 15);
+        assert!(stamp.char_indices().all(|(i, c)| if i == 8 { c == '-' } else { c.is_ascii_digit() }));
+    }
+}
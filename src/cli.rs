@@ -0,0 +1,255 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::aov::AovFormat;
+use crate::debug_view::DebugMode;
+use crate::framebuffer::UpscaleFilter;
+
+pub const USAGE: &str = "\
+usage: proyecto2 [options]
+
+  --mode <name>      render mode: shaded, normals, uv, depth, albedo,
+                     shadows, ids, heatmap, reflections (default shaded)
+  --headless         render a single frame to a PNG without opening a window
+  --out <path>       output image for --headless (default render.png)
+  --time <t>         time of day, 0 = midnight, 0.5 = noon
+  --clock <seconds>  animation time
+  --size <WxH>       window and image size (default 800x600)
+  --aov <dir>        with --headless, write the compositing passes (beauty,
+                     depth, normal, albedo, direct, indirect, shadow,
+                     reflection, refraction, object_id) to <dir>
+                     instead of --out; --denoise is not applied to them
+  --aov-format <f>   png, exr or both (default both)
+  --heightmap <png>  build the valley around the island from a greyscale
+                     image instead of procedural noise
+  --skybox <path>    day sky: a directory with the six faces, a cross image
+                     or an equirectangular .hdr (default textures/skybox/day)
+  --night-skybox <path>
+                     night sky, same formats (default textures/skybox/night)
+  --upscale <f>      filter for the half-resolution frames while moving:
+                     edge-aware or bilinear (default edge-aware)
+  --denoise          apply the edge-aware denoiser to the shaded image
+  --verbose          list every scene warning, including overlapping objects
+  --help             show this message";
+
+#[derive(Debug)]
+pub enum CliError {
+    UnknownFlag(String),
+    MissingValue(String),
+    InvalidValue { flag: String, value: String },
+    // La opción solo tiene sentido junto con otra.
+    Requires { flag: String, required: String },
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CliError::UnknownFlag(flag) => write!(f, "unknown option '{}'", flag),
+            CliError::MissingValue(flag) => write!(f, "missing value for '{}'", flag),
+            CliError::InvalidValue { flag, value } => write!(f, "invalid value '{}' for '{}'", value, flag),
+            CliError::Requires { flag, required } => write!(f, "'{}' requires '{}'", flag, required),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub mode: DebugMode,
+    pub headless: bool,
+    pub output: PathBuf,
+    pub time_of_day: Option<f32>,
+    pub clock_time: f32,
+    pub size: (usize, usize),
+    pub aov_dir: Option<PathBuf>,
+    pub aov_format: AovFormat,
+    pub heightmap: Option<PathBuf>,
+    pub skybox: PathBuf,
+    pub night_skybox: PathBuf,
+    pub upscale: UpscaleFilter,
+    pub denoise: bool,
+    pub verbose: bool,
+    pub help: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            mode: DebugMode::Shaded,
+            headless: false,
+            output: PathBuf::from("render.png"),
+            time_of_day: None,
+            clock_time: 0.0,
+            size: (800, 600),
+            aov_dir: None,
+            aov_format: AovFormat::Both,
+            heightmap: None,
+            skybox: PathBuf::from("textures/skybox/day"),
+            night_skybox: PathBuf::from("textures/skybox/night"),
+            upscale: UpscaleFilter::EdgeAware,
+            denoise: false,
+            verbose: false,
+            help: false,
+        }
+    }
+}
+
+fn parse_size(value: &str) -> Option<(usize, usize)> {
+    let (w, h) = value.split_once(['x', 'X'])?;
+    let size = (w.parse().ok()?, h.parse().ok()?);
+    (size.0 > 0 && size.1 > 0).then_some(size)
+}
+
+/// Lee las opciones de la línea de comandos, sin el nombre del programa.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Options, CliError> {
+    let mut options = Options::default();
+    let mut args = args.into_iter();
+
+    while let Some(flag) = args.next() {
+        if flag == "--headless" {
+            options.headless = true;
+            continue;
+        }
+        if flag == "--denoise" {
+            options.denoise = true;
+            continue;
+        }
+        if flag == "--verbose" {
+            options.verbose = true;
+            continue;
+        }
+        if flag == "--help" || flag == "-h" {
+            options.help = true;
+            continue;
+        }
+
+        let value = match flag.as_str() {
+            "--mode" | "--out" | "--time" | "--clock" | "--size" | "--aov" | "--aov-format" | "--heightmap"
+            | "--skybox" | "--night-skybox" | "--upscale" => {
+                args.next().ok_or_else(|| CliError::MissingValue(flag.clone()))?
+            }
+            _ => return Err(CliError::UnknownFlag(flag)),
+        };
+        let invalid = || CliError::InvalidValue { flag: flag.clone(), value: value.clone() };
+        match flag.as_str() {
+            "--mode" => options.mode = DebugMode::from_name(&value).ok_or_else(invalid)?,
+            "--out" => options.output = PathBuf::from(&value),
+            "--time" => options.time_of_day = Some(value.parse().map_err(|_| invalid())?),
+            "--clock" => options.clock_time = value.parse().map_err(|_| invalid())?,
+            "--size" => options.size = parse_size(&value).ok_or_else(invalid)?,
+            "--aov" => options.aov_dir = Some(PathBuf::from(&value)),
+            "--heightmap" => options.heightmap = Some(PathBuf::from(&value)),
+            "--skybox" => options.skybox = PathBuf::from(&value),
+            "--night-skybox" => options.night_skybox = PathBuf::from(&value),
+            "--aov-format" => {
+                options.aov_format = match value.as_str() {
+                    "png" => AovFormat::Png,
+                    "exr" => AovFormat::Exr,
+                    "both" => AovFormat::Both,
+                    _ => return Err(invalid()),
+                }
+            }
+            "--upscale" => {
+                options.upscale = match value.as_str() {
+                    "edge-aware" => UpscaleFilter::EdgeAware,
+                    "bilinear" => UpscaleFilter::Bilinear,
+                    _ => return Err(invalid()),
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    if options.aov_dir.is_some() && !options.headless {
+        return Err(CliError::Requires { flag: "--aov".to_string(), required: "--headless".to_string() });
+    }
+    Ok(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_args(args: &[&str]) -> Result<Options, CliError> {
+        parse(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn defaults_without_arguments() {
+        let options = parse_args(&[]).unwrap();
+        assert_eq!(options.mode, DebugMode::Shaded);
+        assert!(!options.headless && !options.denoise && !options.verbose && !options.help);
+        assert_eq!(options.output, PathBuf::from("render.png"));
+        assert_eq!(options.size, (800, 600));
+        assert_eq!(options.time_of_day, None);
+        assert_eq!(options.heightmap, None);
+        assert_eq!(options.skybox, PathBuf::from("textures/skybox/day"));
+        assert_eq!(options.night_skybox, PathBuf::from("textures/skybox/night"));
+        assert_eq!(options.upscale, UpscaleFilter::EdgeAware);
+    }
+
+    #[test]
+    fn flags_and_values() {
+        let options = parse_args(&[
+            "--headless", "--mode", "depth", "--out", "a.png", "--time", "0.25", "--clock", "3",
+            "--size", "320X240", "--aov", "passes", "--aov-format", "exr", "--heightmap", "valley.png",
+            "--skybox", "sky.hdr", "--night-skybox", "stars", "--upscale", "bilinear",
+            "--denoise", "--verbose",
+        ])
+        .unwrap();
+        assert!(options.headless && options.denoise && options.verbose);
+        assert_eq!(options.mode, DebugMode::Depth);
+        assert_eq!(options.output, PathBuf::from("a.png"));
+        assert_eq!(options.time_of_day, Some(0.25));
+        assert_eq!(options.clock_time, 3.0);
+        assert_eq!(options.size, (320, 240));
+        assert_eq!(options.aov_dir, Some(PathBuf::from("passes")));
+        assert_eq!(options.aov_format, AovFormat::Exr);
+        assert_eq!(options.heightmap, Some(PathBuf::from("valley.png")));
+        assert_eq!(options.skybox, PathBuf::from("sky.hdr"));
+        assert_eq!(options.night_skybox, PathBuf::from("stars"));
+        assert_eq!(options.upscale, UpscaleFilter::Bilinear);
+    }
+
+    #[test]
+    fn help_flags() {
+        assert!(parse_args(&["--help"]).unwrap().help);
+        assert!(parse_args(&["-h"]).unwrap().help);
+    }
+
+    #[test]
+    fn unknown_flag() {
+        assert!(matches!(parse_args(&["--fast"]), Err(CliError::UnknownFlag(f)) if f == "--fast"));
+    }
+
+    #[test]
+    fn missing_value() {
+        assert!(matches!(parse_args(&["--out"]), Err(CliError::MissingValue(f)) if f == "--out"));
+    }
+
+    #[test]
+    fn invalid_values() {
+        for args in [
+            ["--mode", "wireframe"],
+            ["--time", "noon"],
+            ["--clock", "x"],
+            ["--size", "800"],
+            ["--size", "0x600"],
+            ["--aov-format", "tiff"],
+            ["--upscale", "nearest"],
+        ] {
+            assert!(
+                matches!(parse_args(&args), Err(CliError::InvalidValue { ref flag, ref value }) if flag == args[0] && value == args[1]),
+                "{:?}",
+                args
+            );
+        }
+    }
+
+    #[test]
+    fn aov_requires_headless() {
+        assert!(matches!(parse_args(&["--aov", "passes"]), Err(CliError::Requires { .. })));
+        assert!(parse_args(&["--aov", "passes", "--headless"]).is_ok());
+    }
+}
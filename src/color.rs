@@ -1,4 +1,5 @@
 use std::fmt;
+use nalgebra_glm::Vec3;
 
 #[derive(Debug, Clone, Copy)]
 pub struct Color {
@@ -23,6 +24,20 @@ impl Color {
         Color { r: 0, g: 0, b: 0 }
     }
 
+    // Componentes en [0, 1], redondeadas al entero más cercano.
+    pub fn from_vec3(v: &Vec3) -> Self {
+        let channel = |c: f32| (c * 255.0 + 0.5).clamp(0.0, 255.0) as u8;
+        Color {
+            r: channel(v.x),
+            g: channel(v.y),
+            b: channel(v.z),
+        }
+    }
+
+    pub fn to_vec3(self) -> Vec3 {
+        Vec3::new(self.r as f32 / 255.0, self.g as f32 / 255.0, self.b as f32 / 255.0)
+    }
+
     pub fn to_hex(&self) -> u32 {
         ((self.r as u32) << 16) | ((self.g as u32) << 8) | (self.b as u32)
     }
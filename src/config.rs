@@ -0,0 +1,114 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+// Formato de configuración sencillo:
+//
+//   # comentario
+//   [seccion]
+//   clave = valor
+//
+// Una clave puede repetirse; las entradas se devuelven en el orden del archivo.
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io { path: PathBuf, source: io::Error },
+    Syntax { line: usize, message: String },
+    InvalidValue { line: usize, key: String, value: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io { path, source } => write!(f, "failed to read {}: {}", path.display(), source),
+            ConfigError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+            ConfigError::InvalidValue { line, key, value } => {
+                write!(f, "line {}: invalid value '{}' for '{}'", line, value, key)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ConfigEntry {
+    pub section: String,
+    pub key: String,
+    pub value: String,
+    pub line: usize,
+}
+
+impl ConfigEntry {
+    pub fn invalid(&self) -> ConfigError {
+        ConfigError::InvalidValue { line: self.line, key: self.key.clone(), value: self.value.clone() }
+    }
+
+    pub fn parse<T: FromStr>(&self) -> Result<T, ConfigError> {
+        self.value.parse().map_err(|_| self.invalid())
+    }
+
+    /// Valor formado por números separados por espacios o comas.
+    pub fn numbers(&self) -> Result<Vec<f32>, ConfigError> {
+        self.value
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|part| !part.is_empty())
+            .map(|part| part.parse().map_err(|_| self.invalid()))
+            .collect()
+    }
+}
+
+pub fn parse(text: &str) -> Result<Vec<ConfigEntry>, ConfigError> {
+    let mut section = String::new();
+    let mut entries = Vec::new();
+
+    for (index, raw) in text.lines().enumerate() {
+        let line = index + 1;
+        let content = raw.split('#').next().unwrap_or("").trim();
+        if content.is_empty() {
+            continue;
+        }
+
+        if let Some(rest) = content.strip_prefix('[') {
+            let name = rest.strip_suffix(']').ok_or_else(|| ConfigError::Syntax {
+                line,
+                message: format!("unterminated section header '{}'", content),
+            })?;
+            section = name.trim().to_ascii_lowercase();
+            continue;
+        }
+
+        let (key, value) = content.split_once('=').ok_or_else(|| ConfigError::Syntax {
+            line,
+            message: format!("expected 'key = value', found '{}'", content),
+        })?;
+        let key = key.trim();
+        if key.is_empty() {
+            return Err(ConfigError::Syntax { line, message: "empty key".to_string() });
+        }
+
+        entries.push(ConfigEntry {
+            section: section.clone(),
+            key: key.to_ascii_lowercase(),
+            value: value.trim().to_string(),
+            line,
+        });
+    }
+
+    Ok(entries)
+}
+
+pub fn load(path: impl AsRef<Path>) -> Result<Vec<ConfigEntry>, ConfigError> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path)
+        .map_err(|source| ConfigError::Io { path: path.to_path_buf(), source })?;
+    parse(&text)
+}
@@ -0,0 +1,239 @@
+use std::rc::Rc;
+use nalgebra_glm::Vec3;
+
+use crate::geometry::Aabb;
+use crate::ray_intersect::{Boundary, Intersect, RayIntersect, Span};
+
+// Distancia mínima para aceptar un impacto y no volver a chocar con la
+// superficie de la que sale el rayo.
+const HIT_EPSILON: f32 = 1e-4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsgOp {
+    Union,
+    Intersection,
+    // El primer operando menos el segundo.
+    Difference,
+}
+
+impl CsgOp {
+    fn inside(&self, in_left: bool, in_right: bool) -> bool {
+        match self {
+            CsgOp::Union => in_left || in_right,
+            CsgOp::Intersection => in_left && in_right,
+            CsgOp::Difference => in_left && !in_right,
+        }
+    }
+}
+
+/// Operación booleana entre dos sólidos. Los operandos deben implementar
+/// `spans` y `surface_at`; si alguno no lo hace, el nodo no produce impactos.
+#[derive(Clone)]
+pub struct Csg {
+    pub op: CsgOp,
+    pub left: Rc<dyn RayIntersect>,
+    pub right: Rc<dyn RayIntersect>,
+    // Se calcula una vez: con varios niveles anidados recorrerlos en cada
+    // rayo costaría tanto como intersectar.
+    bounds: Option<Aabb>,
+}
+
+impl Csg {
+    pub fn new(op: CsgOp, left: Rc<dyn RayIntersect>, right: Rc<dyn RayIntersect>) -> Self {
+        let bounds = left.bounds().and_then(|left_bounds| match op {
+            CsgOp::Union => Some(left_bounds.union(&right.bounds()?)),
+            CsgOp::Intersection | CsgOp::Difference => Some(left_bounds),
+        });
+        Csg { op, left, right, bounds }
+    }
+
+    pub fn union(left: impl RayIntersect + 'static, right: impl RayIntersect + 'static) -> Self {
+        Csg::new(CsgOp::Union, Rc::new(left), Rc::new(right))
+    }
+
+    pub fn intersection(left: impl RayIntersect + 'static, right: impl RayIntersect + 'static) -> Self {
+        Csg::new(CsgOp::Intersection, Rc::new(left), Rc::new(right))
+    }
+
+    pub fn difference(left: impl RayIntersect + 'static, right: impl RayIntersect + 'static) -> Self {
+        Csg::new(CsgOp::Difference, Rc::new(left), Rc::new(right))
+    }
+
+    /// Resta varios sólidos a la vez, p. ej. huecos de puertas y ventanas.
+    pub fn subtract_all(base: impl RayIntersect + 'static, cutters: Vec<Rc<dyn RayIntersect>>) -> Rc<dyn RayIntersect> {
+        cutters.into_iter().fold(Rc::new(base), |acc, cutter| Rc::new(Csg::new(CsgOp::Difference, acc, cutter)))
+    }
+}
+
+impl RayIntersect for Csg {
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
+        let spans = match self.spans(ray_origin, ray_direction) {
+            Some(spans) => spans,
+            None => return Intersect::empty(),
+        };
+        let boundary = spans
+            .into_iter()
+            .flat_map(|span| [span.enter, span.exit])
+            .find(|boundary| boundary.distance > HIT_EPSILON);
+        // El material solo se busca para el impacto que se devuelve
+        match boundary.and_then(|b| Some((b, self.surface_at(ray_origin, ray_direction, b.distance)?))) {
+            Some((boundary, mut hit)) => {
+                hit.normal = boundary.normal;
+                hit
+            }
+            None => Intersect::empty(),
+        }
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        self.bounds
+    }
+
+    fn spans(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<Vec<Span>> {
+        // Los tramos que quedan detrás del origen no cambian el resultado por delante
+        if self.bounds.is_some_and(|b| b.ray_range(ray_origin, ray_direction).is_none()) {
+            return Some(Vec::new());
+        }
+        let left = self.left.spans(ray_origin, ray_direction)?;
+        if left.is_empty() && self.op != CsgOp::Union {
+            return Some(Vec::new());
+        }
+        let right = self.right.spans(ray_origin, ray_direction)?;
+
+        // Eventos de entrada y salida de ambos operandos, ordenados por distancia
+        let mut events: Vec<(Boundary, bool, bool)> = Vec::with_capacity(2 * (left.len() + right.len()));
+        for (spans, is_left) in [(left, true), (right, false)] {
+            for span in spans {
+                events.push((span.enter, is_left, true));
+                events.push((span.exit, is_left, false));
+            }
+        }
+        events.sort_by(|a, b| a.0.distance.total_cmp(&b.0.distance));
+
+        let (mut in_left, mut in_right) = (false, false);
+        let mut result = Vec::new();
+        let mut enter: Option<Boundary> = None;
+        for (mut hit, is_left, entering) in events {
+            let was_inside = self.op.inside(in_left, in_right);
+            if is_left {
+                in_left = entering;
+            } else {
+                in_right = entering;
+            }
+            let inside = self.op.inside(in_left, in_right);
+            if inside == was_inside {
+                continue;
+            }
+
+            // Las caras del sólido restado miran hacia dentro de él
+            if !is_left && self.op == CsgOp::Difference {
+                hit.normal = -hit.normal;
+            }
+            if inside {
+                enter = Some(hit);
+            } else if let Some(enter) = enter.take() {
+                result.push(Span { enter, exit: hit });
+            }
+        }
+        Some(result)
+    }
+
+    // El extremo a `distance` viene del operando con un borde a esa distancia;
+    // se mira primero el derecho, que en las restas suele ser una sola caja.
+    fn surface_at(&self, ray_origin: &Vec3, ray_direction: &Vec3, distance: f32) -> Option<Intersect> {
+        let gap = |spans: Option<Vec<Span>>| {
+            spans
+                .unwrap_or_default()
+                .iter()
+                .flat_map(|span| [span.enter.distance, span.exit.distance])
+                .map(|d| (d - distance).abs())
+                .fold(f32::INFINITY, f32::min)
+        };
+        let right_gap = gap(self.right.spans(ray_origin, ray_direction));
+        let operand = if right_gap < HIT_EPSILON || right_gap < gap(self.left.spans(ray_origin, ray_direction)) {
+            &self.right
+        } else {
+            &self.left
+        };
+        operand.surface_at(ray_origin, ray_direction, distance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Material;
+    use crate::sphere::Sphere;
+
+    const ORIGIN: Vec3 = Vec3::new(-10.0, 0.0, 0.0);
+    const DIRECTION: Vec3 = Vec3::new(1.0, 0.0, 0.0);
+
+    fn ball(x: f32, color: u8) -> Sphere {
+        Sphere::new(Vec3::new(x, 0.0, 0.0), 1.0, Material::new([1.0, 0.0, 0.0, 0.0], [color; 3], 10.0, 1.0, None))
+    }
+
+    fn distances(csg: &Csg) -> Vec<(f32, f32)> {
+        csg.spans(&ORIGIN, &DIRECTION)
+            .unwrap()
+            .iter()
+            .map(|span| (span.enter.distance, span.exit.distance))
+            .collect()
+    }
+
+    fn assert_spans(actual: Vec<(f32, f32)>, expected: &[(f32, f32)]) {
+        assert_eq!(actual.len(), expected.len(), "{:?}", actual);
+        for ((a0, a1), (e0, e1)) in actual.iter().zip(expected) {
+            assert!((a0 - e0).abs() < 1e-4 && (a1 - e1).abs() < 1e-4, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn union_merges_overlapping_spans() {
+        assert_spans(distances(&Csg::union(ball(0.0, 1), ball(1.5, 2))), &[(9.0, 12.5)]);
+        assert_spans(distances(&Csg::union(ball(0.0, 1), ball(5.0, 2))), &[(9.0, 11.0), (14.0, 16.0)]);
+    }
+
+    #[test]
+    fn intersection_keeps_the_overlap() {
+        assert_spans(distances(&Csg::intersection(ball(0.0, 1), ball(1.5, 2))), &[(10.5, 11.0)]);
+        assert_spans(distances(&Csg::intersection(ball(0.0, 1), ball(5.0, 2))), &[]);
+    }
+
+    #[test]
+    fn difference_flips_the_cutter_normal() {
+        let csg = Csg::difference(ball(0.0, 1), ball(1.0, 2));
+        let spans = csg.spans(&ORIGIN, &DIRECTION).unwrap();
+        assert_spans(distances(&csg), &[(9.0, 10.0)]);
+        assert!((spans[0].exit.normal - DIRECTION).norm() < 1e-4);
+    }
+
+    #[test]
+    fn empty_left_operand_gives_no_spans() {
+        let missed = || Sphere::new(Vec3::new(0.0, 5.0, 0.0), 1.0, ball(0.0, 1).material);
+        assert_spans(distances(&Csg::difference(missed(), ball(0.0, 2))), &[]);
+        assert_spans(distances(&Csg::intersection(missed(), ball(0.0, 2))), &[]);
+        assert_spans(distances(&Csg::union(missed(), ball(0.0, 2))), &[(9.0, 11.0)]);
+    }
+
+    #[test]
+    fn hit_uses_the_operand_that_owns_the_surface() {
+        // Por delante se entra en el primer operando
+        let hit = Csg::difference(ball(0.0, 1), ball(-1.5, 2)).ray_intersect(&ORIGIN, &DIRECTION);
+        assert!(hit.is_intersecting);
+        assert!((hit.distance - 9.5).abs() < 1e-4);
+        assert_eq!(hit.material.diffuse_color, [2; 3]);
+        assert!((hit.normal + DIRECTION).norm() < 1e-4);
+
+        let hit = Csg::intersection(ball(0.0, 1), ball(1.5, 2)).ray_intersect(&ORIGIN, &DIRECTION);
+        assert!((hit.distance - 10.5).abs() < 1e-4);
+        assert_eq!(hit.material.diffuse_color, [2; 3]);
+    }
+
+    #[test]
+    fn ray_outside_the_bounds_misses() {
+        let csg = Csg::union(ball(0.0, 1), ball(1.5, 2));
+        let up = Vec3::new(0.0, 1.0, 0.0);
+        assert_eq!(csg.spans(&ORIGIN, &up).unwrap().len(), 0);
+        assert!(!csg.ray_intersect(&ORIGIN, &up).is_intersecting);
+    }
+}
@@ -1,8 +1,10 @@
 use nalgebra_glm::Vec3;
-use crate::ray_intersect::{RayIntersect, Intersect};
+use crate::ray_intersect::{RayIntersect, Intersect, Boundary, Span};
 use crate::texture::Texture;
 use std::rc::Rc;
-use crate::material::Material;
+use crate::material::{Mapping, Material};
+use crate::procedural::ProceduralTexture;
+use crate::geometry::{Aabb, GeometryError};
 
 
 #[derive(Clone)]
@@ -12,19 +14,68 @@ pub struct Cube {
     pub top_texture: Rc<Texture>,    
     pub bottom_texture: Rc<Texture>,
     pub side_texture: Rc<Texture>,   
+    // Texturas procedurales por cara; sustituyen a la imagen de esa cara.
+    pub top_procedural: Option<Rc<dyn ProceduralTexture>>,
+    pub bottom_procedural: Option<Rc<dyn ProceduralTexture>>,
+    pub side_procedural: Option<Rc<dyn ProceduralTexture>>,
+    pub mapping: Mapping,
 }
 
 impl Cube {
+    // Las esquinas pueden darse en cualquier orden; se reordenan por componente.
     pub fn new(min: Vec3, max: Vec3, top_texture: Rc<Texture>, bottom_texture: Rc<Texture>, side_texture: Rc<Texture>) -> Self {
+        let bounds = Aabb::new(min, max);
         Cube {
-            min,
-            max,
+            min: bounds.min,
+            max: bounds.max,
             top_texture,
             bottom_texture,
             side_texture,
+            top_procedural: None,
+            bottom_procedural: None,
+            side_procedural: None,
+            mapping: Mapping::Uv,
         }
     }
 
+    /// Como `new`, pero rechaza cajas sin volumen o con coordenadas no finitas.
+    pub fn try_new(min: Vec3, max: Vec3, top_texture: Rc<Texture>, bottom_texture: Rc<Texture>, side_texture: Rc<Texture>) -> Result<Self, GeometryError> {
+        // Se comprueba antes de reordenar: min/max por componente descartan los NaN
+        if !min.iter().chain(max.iter()).all(|c| c.is_finite()) {
+            return Err(GeometryError::NonFinite { min, max });
+        }
+        let bounds = Aabb::new(min, max);
+        if bounds.is_degenerate() {
+            return Err(GeometryError::Degenerate { min, max });
+        }
+        Ok(Cube::new(min, max, top_texture, bottom_texture, side_texture))
+    }
+
+    // Sustituye las texturas de imagen por una procedural en espacio del objeto.
+    pub fn with_procedural(mut self, procedural: Rc<dyn ProceduralTexture>) -> Self {
+        self.top_procedural = Some(procedural.clone());
+        self.bottom_procedural = Some(procedural.clone());
+        self.side_procedural = Some(procedural);
+        self
+    }
+
+    pub fn with_top_procedural(mut self, procedural: Rc<dyn ProceduralTexture>) -> Self {
+        self.top_procedural = Some(procedural);
+        self
+    }
+
+    pub fn with_side_procedural(mut self, procedural: Rc<dyn ProceduralTexture>) -> Self {
+        self.side_procedural = Some(procedural);
+        self
+    }
+
+    // Con `Triplanar` o `Solid` la textura depende de la posición en el mundo,
+    // así que cubos contiguos quedan continuos aunque estén partidos.
+    pub fn with_mapping(mut self, mapping: Mapping) -> Self {
+        self.mapping = mapping;
+        self
+    }
+
     fn get_texture(&self, normal: &Vec3) -> &Rc<Texture> {
         if normal.y.abs() > 0.9 {
             if normal.y > 0.0 {
@@ -37,6 +88,18 @@ impl Cube {
         }
     }
 
+    fn get_procedural(&self, normal: &Vec3) -> Option<&Rc<dyn ProceduralTexture>> {
+        if normal.y.abs() > 0.9 {
+            if normal.y > 0.0 {
+                self.top_procedural.as_ref()
+            } else {
+                self.bottom_procedural.as_ref()
+            }
+        } else {
+            self.side_procedural.as_ref()
+        }
+    }
+
     fn get_uv(&self, point: &Vec3, normal: &Vec3) -> (f32, f32) {
         let (u, v) = if normal.y.abs() > 0.9 {
             let u = (point.x - self.min.x) / (self.max.x - self.min.x);
@@ -79,8 +142,9 @@ impl Cube {
     }
 }
 
-impl RayIntersect for Cube {
-    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
+impl Cube {
+    // Distancias de entrada y salida del rayo por el método de los planos.
+    fn slab_distances(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<(f32, f32)> {
         let mut t_min = (self.min.x - ray_origin.x) / ray_direction.x;
         let mut t_max = (self.max.x - ray_origin.x) / ray_direction.x;
 
@@ -96,7 +160,7 @@ impl RayIntersect for Cube {
         }
 
         if (t_min > t_y_max) || (t_y_min > t_max) {
-            return Intersect::empty();
+            return None;
         }
 
         if t_y_min > t_min {
@@ -115,36 +179,136 @@ impl RayIntersect for Cube {
         }
 
         if (t_min > t_z_max) || (t_z_min > t_max) {
-            return Intersect::empty();
+            return None;
         }
 
         if t_z_min > t_min {
             t_min = t_z_min;
         }
 
-        if t_min < 0.0 {
-            return Intersect::empty();
+        if t_z_max < t_max {
+            t_max = t_z_max;
         }
 
-        let point_on_surface = ray_origin + ray_direction * t_min;
+        Some((t_min, t_max))
+    }
+
+    fn boundary(&self, ray_origin: &Vec3, ray_direction: &Vec3, distance: f32) -> Boundary {
+        Boundary { distance, normal: self.calculate_normal(ray_origin + ray_direction * distance) }
+    }
+
+    fn hit_at(&self, ray_origin: &Vec3, ray_direction: &Vec3, distance: f32) -> Intersect {
+        let point_on_surface = ray_origin + ray_direction * distance;
         let normal = self.calculate_normal(point_on_surface);
         let texture = self.get_texture(&normal);
         let (u, v) = self.get_uv(&point_on_surface, &normal);
 
+        let mut material = Material::new(
+            [0.9, 0.1, 0.0, 0.0],  // albedo
+            [255, 255, 255],       // diffuse_color
+            10.0,                  // specular
+            1.0,                   // refractive_index
+            Some(texture.clone())  // texture
+        ).with_mapping(self.mapping);
+        if let Some(procedural) = self.get_procedural(&normal) {
+            material = material.with_procedural(procedural.clone());
+        }
+
         Intersect {
             point: point_on_surface,
+            local_point: point_on_surface - self.min,
             normal,
-            distance: t_min,
-            material: Material::new(
-                [1.0, 1.0, 1.0, 1.0],  // albedo
-                [255, 255, 255],       // diffuse_color
-                0.5,                   // specular
-                1.0,                   // refractive_index
-                Some(texture.clone())  // texture
-            ), 
+            distance,
+            material, 
             is_intersecting: true,
             u: Some(u),
             v: Some(v),
         }
     }
 }
+
+impl RayIntersect for Cube {
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
+        match self.slab_distances(ray_origin, ray_direction) {
+            Some((t_min, _)) if t_min >= 0.0 => self.hit_at(ray_origin, ray_direction, t_min),
+            _ => Intersect::empty(),
+        }
+    }
+
+    fn spans(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<Vec<Span>> {
+        let spans = match self.slab_distances(ray_origin, ray_direction) {
+            Some((t_min, t_max)) => vec![Span {
+                enter: self.boundary(ray_origin, ray_direction, t_min),
+                exit: self.boundary(ray_origin, ray_direction, t_max),
+            }],
+            None => Vec::new(),
+        };
+        Some(spans)
+    }
+
+    fn surface_at(&self, ray_origin: &Vec3, ray_direction: &Vec3, distance: f32) -> Option<Intersect> {
+        Some(self.hit_at(ray_origin, ray_direction, distance))
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        Some(Aabb { min: self.min, max: self.max })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::GeometryError;
+    use crate::procedural::{ColorRamp, Gradient};
+
+    fn checked(min: Vec3, max: Vec3) -> Result<Cube, GeometryError> {
+        let texture = Rc::new(Texture::new("textures/snow.png"));
+        Cube::try_new(min, max, texture.clone(), texture.clone(), texture)
+    }
+
+    // Color de la cara superior de `cube` en (x, z)
+    fn top_color(cube: &Cube, x: f32, z: f32) -> [u8; 3] {
+        let hit = cube.ray_intersect(&Vec3::new(x, 5.0, z), &Vec3::new(0.0, -1.0, 0.0));
+        assert!(hit.is_intersecting);
+        hit.surface_color()
+    }
+
+    #[test]
+    fn inverted_bounds_are_normalized() {
+        let cube = checked(Vec3::new(-2.0, 5.0, -2.0), Vec3::new(6.0, 4.0, 3.0)).unwrap();
+        assert_eq!(cube.min, Vec3::new(-2.0, 4.0, -2.0));
+        assert_eq!(cube.max, Vec3::new(6.0, 5.0, 3.0));
+    }
+
+    #[test]
+    fn flat_and_non_finite_bounds_are_rejected() {
+        let flat = (Vec3::new(0.0, 1.0, 0.0), Vec3::new(2.0, 1.0, 2.0));
+        assert_eq!(checked(flat.0, flat.1).err(), Some(GeometryError::Degenerate { min: flat.0, max: flat.1 }));
+
+        let nan = (Vec3::new(0.0, f32::NAN, 0.0), Vec3::new(1.0, 1.0, 1.0));
+        assert!(matches!(checked(nan.0, nan.1), Err(GeometryError::NonFinite { .. })));
+    }
+
+    #[test]
+    fn solid_mapping_is_continuous_across_adjacent_cubes() {
+        let gradient: Rc<dyn ProceduralTexture> =
+            Rc::new(Gradient::new(Vec3::new(1.0, 0.0, 0.0), 0.0, 2.0, ColorRamp::two([0, 0, 0], [255, 255, 255])));
+        let pair = |mapping| {
+            let left = checked(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0)).unwrap();
+            let right = checked(Vec3::new(1.0, 0.0, 0.0), Vec3::new(2.0, 1.0, 1.0)).unwrap();
+            (
+                left.with_procedural(gradient.clone()).with_mapping(mapping),
+                right.with_procedural(gradient.clone()).with_mapping(mapping),
+            )
+        };
+
+        let (left, right) = pair(Mapping::Solid);
+        let a = top_color(&left, 0.999, 0.5);
+        let b = top_color(&right, 1.001, 0.5);
+        assert!(a.iter().zip(b).all(|(a, b)| a.abs_diff(b) <= 1), "{:?} vs {:?}", a, b);
+
+        // Con UV cada cubo empieza el degradado en su propia esquina
+        let (left, right) = pair(Mapping::Uv);
+        assert!(top_color(&left, 0.999, 0.5)[0].abs_diff(top_color(&right, 1.001, 0.5)[0]) > 100);
+    }
+}
@@ -0,0 +1,281 @@
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+
+use crate::config::{self, ConfigError};
+use crate::light::Light;
+use crate::math::smoothstep;
+
+#[derive(Debug, Clone, Copy)]
+pub struct LightKeyframe {
+    pub time: f32,
+    pub color: [u8; 3],
+    pub intensity: f32,
+}
+
+impl LightKeyframe {
+    pub fn new(time: f32, color: [u8; 3], intensity: f32) -> Self {
+        LightKeyframe { time, color, intensity }
+    }
+}
+
+/// Claves de color e intensidad a lo largo de un día; se repite cíclicamente.
+#[derive(Debug, Clone, Default)]
+pub struct LightTrack {
+    keys: Vec<LightKeyframe>,
+}
+
+impl LightTrack {
+    pub fn new(mut keys: Vec<LightKeyframe>) -> Self {
+        keys.sort_by(|a, b| a.time.total_cmp(&b.time));
+        LightTrack { keys }
+    }
+
+    pub fn eval(&self, time_of_day: f32) -> ([u8; 3], f32) {
+        let count = self.keys.len();
+        if count == 0 {
+            return ([0, 0, 0], 0.0);
+        }
+        if count == 1 {
+            return (self.keys[0].color, self.keys[0].intensity);
+        }
+
+        let t = time_of_day.rem_euclid(1.0);
+        let next = self.keys.iter().position(|k| k.time > t).unwrap_or(0);
+        let current = (next + count - 1) % count;
+        let (a, b) = (&self.keys[current], &self.keys[next]);
+
+        let span = (b.time - a.time).rem_euclid(1.0);
+        let s = if span > 0.0 { (t - a.time).rem_euclid(1.0) / span } else { 0.0 };
+        let channel = |i: usize| (a.color[i] as f32 + (b.color[i] as f32 - a.color[i] as f32) * s) as u8;
+        ([channel(0), channel(1), channel(2)], a.intensity + (b.intensity - a.intensity) * s)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeSource {
+    // Un día completo dura `day_length` segundos reales.
+    Accelerated { day_length: f32 },
+    // Hora del reloj del sistema con un desfase respecto a UTC.
+    RealClock { utc_offset_hours: f32 },
+}
+
+/// Ciclo de día y noche: posición del sol según latitud y época del año,
+/// luna opuesta al sol y colores por claves para ambos.
+#[derive(Debug, Clone)]
+pub struct DayCycle {
+    pub time_of_day: f32,
+    pub time_source: TimeSource,
+    pub orbit_radius: f32,
+    pub latitude: f32,
+    pub day_of_year: f32,
+    pub sun: LightTrack,
+    pub moon: LightTrack,
+}
+
+impl Default for DayCycle {
+    fn default() -> Self {
+        DayCycle {
+            time_of_day: 0.5,
+            time_source: TimeSource::Accelerated { day_length: 240.0 },
+            orbit_radius: 15.0,
+            latitude: 14.6,
+            day_of_year: 80.0,
+            sun: LightTrack::new(vec![
+                LightKeyframe::new(0.00, [40, 50, 90], 0.0),
+                LightKeyframe::new(0.22, [255, 120, 60], 0.3),
+                LightKeyframe::new(0.28, [255, 200, 140], 0.8),
+                LightKeyframe::new(0.35, [255, 250, 240], 1.0),
+                LightKeyframe::new(0.65, [255, 250, 240], 1.0),
+                LightKeyframe::new(0.72, [255, 190, 120], 0.8),
+                LightKeyframe::new(0.78, [255, 110, 60], 0.3),
+            ]),
+            moon: LightTrack::new(vec![
+                LightKeyframe::new(0.0, [150, 170, 220], 0.25),
+                LightKeyframe::new(0.5, [150, 170, 220], 0.25),
+            ]),
+        }
+    }
+}
+
+impl DayCycle {
+    /// Carga el ciclo desde un archivo de configuración; lo que no aparezca
+    /// conserva el valor por defecto.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let mut cycle = DayCycle::default();
+        let mut sun_keys = Vec::new();
+        let mut moon_keys = Vec::new();
+        let mut day_length = 240.0;
+        let mut utc_offset_hours = 0.0;
+        let mut real_clock = false;
+
+        for entry in config::load(path)? {
+            match (entry.section.as_str(), entry.key.as_str()) {
+                ("cycle", "start") => cycle.time_of_day = entry.parse()?,
+                ("cycle", "orbit_radius") => cycle.orbit_radius = entry.parse()?,
+                ("cycle", "latitude") => cycle.latitude = entry.parse()?,
+                ("cycle", "day_of_year") => cycle.day_of_year = entry.parse()?,
+                ("cycle", "day_length") => day_length = entry.parse()?,
+                ("cycle", "utc_offset") => utc_offset_hours = entry.parse()?,
+                ("cycle", "clock") => {
+                    real_clock = match entry.value.as_str() {
+                        "real" => true,
+                        "accelerated" => false,
+                        _ => return Err(entry.invalid()),
+                    }
+                }
+                ("sun", "key") | ("moon", "key") => {
+                    let values = entry.numbers()?;
+                    if values.len() != 5 {
+                        return Err(entry.invalid());
+                    }
+                    let key = LightKeyframe::new(
+                        values[0],
+                        [values[1] as u8, values[2] as u8, values[3] as u8],
+                        values[4],
+                    );
+                    if entry.section == "sun" {
+                        sun_keys.push(key);
+                    } else {
+                        moon_keys.push(key);
+                    }
+                }
+                _ => return Err(entry.invalid()),
+            }
+        }
+
+        cycle.time_source = if real_clock {
+            TimeSource::RealClock { utc_offset_hours }
+        } else {
+            TimeSource::Accelerated { day_length }
+        };
+        if !sun_keys.is_empty() {
+            cycle.sun = LightTrack::new(sun_keys);
+        }
+        if !moon_keys.is_empty() {
+            cycle.moon = LightTrack::new(moon_keys);
+        }
+        Ok(cycle)
+    }
+
+    /// Avanza la hora `dt` segundos reales.
+    pub fn advance(&mut self, dt: f32) {
+        self.time_of_day = match self.time_source {
+            TimeSource::Accelerated { day_length } if day_length > 0.0 => {
+                (self.time_of_day + dt / day_length).rem_euclid(1.0)
+            }
+            TimeSource::Accelerated { .. } => self.time_of_day,
+            TimeSource::RealClock { utc_offset_hours } => {
+                let seconds = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs_f64())
+                    .unwrap_or(0.0);
+                ((seconds + utc_offset_hours as f64 * 3600.0).rem_euclid(86_400.0) / 86_400.0) as f32
+            }
+        };
+    }
+
+    pub fn set_time(&mut self, time_of_day: f32) {
+        self.time_of_day = time_of_day.rem_euclid(1.0);
+    }
+
+    /// Dirección hacia el sol: +x es el este, +y el cenit y -z el norte.
+    pub fn sun_direction(&self) -> Vec3 {
+        let latitude = self.latitude.to_radians();
+        let declination = (-23.44f32).to_radians() * (2.0 * PI / 365.0 * (self.day_of_year + 10.0)).cos();
+        let hour_angle = (self.time_of_day - 0.5) * 2.0 * PI;
+
+        let east = -declination.cos() * hour_angle.sin();
+        let north = latitude.cos() * declination.sin() - latitude.sin() * declination.cos() * hour_angle.cos();
+        let up = latitude.sin() * declination.sin() + latitude.cos() * declination.cos() * hour_angle.cos();
+        Vec3::new(east, up, -north).normalize()
+    }
+
+    pub fn moon_direction(&self) -> Vec3 {
+        let sun = self.sun_direction();
+        Vec3::new(-sun.x, -sun.y, -sun.z + 0.2).normalize()
+    }
+
+    fn place(light: &mut Light, direction: &Vec3, orbit_radius: f32, track: &LightTrack, time_of_day: f32) {
+        let (color, intensity) = track.eval(time_of_day);
+        light.position = direction * orbit_radius;
+        light.color = color;
+        // Bajo el horizonte la luz se apaga en lugar de seguir iluminando.
+        light.intensity = intensity * smoothstep(-0.05, 0.05, direction.y);
+    }
+
+    pub fn update_sun(&self, light: &mut Light) {
+        Self::place(light, &self.sun_direction(), self.orbit_radius, &self.sun, self.time_of_day);
+    }
+
+    pub fn update_moon(&self, light: &mut Light) {
+        Self::place(light, &self.moon_direction(), self.orbit_radius, &self.moon, self.time_of_day);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Ecuador cerca del equinoccio: el sol pasa casi por el cenit.
+    fn equator(time_of_day: f32) -> DayCycle {
+        DayCycle { time_of_day, latitude: 0.0, day_of_year: 80.0, ..DayCycle::default() }
+    }
+
+    #[test]
+    fn sun_and_moon_at_noon_and_midnight() {
+        let noon = equator(0.5);
+        let sun = noon.sun_direction();
+        assert!(sun.y > 0.999 && sun.x.abs() < 1e-3, "{:?}", sun);
+        assert!(noon.moon_direction().y < -0.9);
+
+        let midnight = equator(0.0);
+        assert!(midnight.sun_direction().y < -0.999);
+        assert!(midnight.moon_direction().y > 0.9);
+
+        // Sale por el este y se pone por el oeste
+        assert!(equator(0.25).sun_direction().x > 0.999);
+        assert!(equator(0.75).sun_direction().x < -0.999);
+    }
+
+    #[test]
+    fn lights_follow_the_sun_and_moon() {
+        let mut sun = Light::new(Vec3::zeros(), [0, 0, 0], 0.0);
+        let mut moon = Light::new(Vec3::zeros(), [0, 0, 0], 0.0);
+
+        let midnight = equator(0.0);
+        midnight.update_sun(&mut sun);
+        midnight.update_moon(&mut moon);
+        assert_eq!(sun.intensity, 0.0);
+        assert!(moon.intensity > 0.0);
+        assert!((moon.position.norm() - midnight.orbit_radius).abs() < 1e-3);
+
+        let noon = equator(0.5);
+        noon.update_sun(&mut sun);
+        assert_eq!((sun.color, sun.intensity), ([255, 250, 240], 1.0));
+        assert!(sun.position.y > 14.9);
+    }
+
+    #[test]
+    fn time_of_day_wraps_around() {
+        let mut cycle = DayCycle { time_source: TimeSource::Accelerated { day_length: 100.0 }, ..equator(0.9) };
+        cycle.advance(20.0);
+        assert!((cycle.time_of_day - 0.1).abs() < 1e-5);
+        cycle.advance(-30.0);
+        assert!((cycle.time_of_day - 0.8).abs() < 1e-5);
+
+        cycle.set_time(1.25);
+        assert_eq!(cycle.time_of_day, 0.25);
+        cycle.set_time(-0.25);
+        assert_eq!(cycle.time_of_day, 0.75);
+
+        // La pista de color interpola entre la última clave y la primera
+        let track = LightTrack::new(vec![LightKeyframe::new(0.2, [0, 0, 0], 0.0), LightKeyframe::new(0.8, [200, 0, 0], 1.0)]);
+        let (color, intensity) = track.eval(1.0);
+        assert_eq!(color, [100, 0, 0]);
+        assert!((intensity - 0.5).abs() < 1e-5);
+        assert_eq!(track.eval(1.0), track.eval(0.0));
+        assert_eq!(track.eval(-0.5), track.eval(0.5));
+    }
+}
@@ -0,0 +1,127 @@
+use nalgebra_glm::Vec3;
+
+use crate::color::Color;
+
+/// Qué se dibuja en cada píxel: la imagen normal o una visualización para
+/// depurar geometría, UV o sombreado.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DebugMode {
+    #[default]
+    Shaded,
+    // Normal de sombreado en mundo, de [-1, 1] a [0, 255].
+    Normals,
+    // Tablero sobre las UV; magenta donde la superficie no tiene UV.
+    UvChecker,
+    // Profundidad lineal sobre el eje de la cámara, blanco cerca.
+    Depth,
+    // Color de la superficie sin luz.
+    Albedo,
+    // Fracción de luces activas que ven el punto.
+    ShadowMask,
+    // Un color por objeto de `Scene::objects`.
+    ObjectId,
+    // Objetos cuya caja cruza el rayo primario, es decir, los que hay que
+    // intersectar de verdad; no hay BVH, así que mide el coste por píxel.
+    Heatmap,
+    // Rebotes de reflexión o refracción que alcanza el rayo; azul es ninguno.
+    ReflectionDepth,
+}
+
+/// Distancia que se ve negra en `DebugMode::Depth`.
+pub const DEPTH_RANGE: f32 = 40.0;
+/// Número de objetos que se ve rojo en `DebugMode::Heatmap`.
+pub const HEATMAP_RANGE: f32 = 12.0;
+// Cuadros del tablero por unidad de UV.
+const CHECKER_CELLS: f32 = 8.0;
+pub const SKY_COLOR: u32 = 0x101018;
+pub const MISSING_UV_COLOR: u32 = 0xFF00FF;
+
+impl DebugMode {
+    pub const ALL: [DebugMode; 9] = [
+        DebugMode::Shaded,
+        DebugMode::Normals,
+        DebugMode::UvChecker,
+        DebugMode::Depth,
+        DebugMode::Albedo,
+        DebugMode::ShadowMask,
+        DebugMode::ObjectId,
+        DebugMode::Heatmap,
+        DebugMode::ReflectionDepth,
+    ];
+
+    /// Nombre para la línea de comandos.
+    pub fn name(self) -> &'static str {
+        match self {
+            DebugMode::Shaded => "shaded",
+            DebugMode::Normals => "normals",
+            DebugMode::UvChecker => "uv",
+            DebugMode::Depth => "depth",
+            DebugMode::Albedo => "albedo",
+            DebugMode::ShadowMask => "shadows",
+            DebugMode::ObjectId => "ids",
+            DebugMode::Heatmap => "heatmap",
+            DebugMode::ReflectionDepth => "reflections",
+        }
+    }
+
+    /// Texto para el HUD.
+    pub fn label(self) -> &'static str {
+        match self {
+            DebugMode::Shaded => "NORMAL",
+            DebugMode::Normals => "NORMALES",
+            DebugMode::UvChecker => "UV",
+            DebugMode::Depth => "PROFUNDIDAD",
+            DebugMode::Albedo => "ALBEDO",
+            DebugMode::ShadowMask => "SOMBRAS",
+            DebugMode::ObjectId => "OBJETOS",
+            DebugMode::Heatmap => "COSTE",
+            DebugMode::ReflectionDepth => "REBOTES",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<DebugMode> {
+        DebugMode::ALL.into_iter().find(|m| m.name() == name)
+    }
+}
+
+fn pack(r: f32, g: f32, b: f32) -> u32 {
+    Color::from_vec3(&Vec3::new(r, g, b)).to_hex()
+}
+
+pub fn normal_color(normal: &Vec3) -> u32 {
+    pack(normal.x * 0.5 + 0.5, normal.y * 0.5 + 0.5, normal.z * 0.5 + 0.5)
+}
+
+pub fn uv_checker_color(u: f32, v: f32) -> u32 {
+    let cell = (u * CHECKER_CELLS).floor() as i32 + (v * CHECKER_CELLS).floor() as i32;
+    let shade = if cell.rem_euclid(2) == 0 { 1.0 } else { 0.5 };
+    let (u, v) = (u.rem_euclid(1.0), v.rem_euclid(1.0));
+    pack((0.25 + 0.75 * u) * shade, (0.25 + 0.75 * v) * shade, 0.5 * shade)
+}
+
+pub fn depth_color(depth: f32) -> u32 {
+    let value = 1.0 - depth / DEPTH_RANGE;
+    pack(value, value, value)
+}
+
+pub fn gray(value: f32) -> u32 {
+    pack(value, value, value)
+}
+
+/// Color estable y distinto para cada índice.
+pub fn id_color(index: usize) -> u32 {
+    let hash = (index as u32 + 1).wrapping_mul(2_654_435_761);
+    let hash = hash ^ (hash >> 15);
+    // Se evita el negro para no confundirlo con el cielo
+    (hash & 0xFFFFFF) | 0x303030
+}
+
+/// Escala azul → verde → amarillo → rojo para `value` en [0, 1].
+pub fn heat_color(value: f32) -> u32 {
+    let t = value.clamp(0.0, 1.0) * 3.0;
+    match t {
+        t if t < 1.0 => pack(0.0, t, 1.0 - t),
+        t if t < 2.0 => pack(t - 1.0, 1.0, 0.0),
+        t => pack(1.0, 3.0 - t, 0.0),
+    }
+}
@@ -0,0 +1,249 @@
+use nalgebra_glm::Vec3;
+
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+use crate::ray_intersect::Intersect;
+
+/// Datos del impacto primario de cada píxel que guían al filtro para no
+/// mezclar a través de bordes de geometría o de textura.
+#[derive(Debug, Clone, Default)]
+pub struct Guides {
+    pub width: usize,
+    pub height: usize,
+    pub normal: Vec<Vec3>,
+    pub albedo: Vec<Vec3>,
+    // Distancia al impacto; infinito donde se ve el cielo.
+    pub depth: Vec<f32>,
+    // Punto de impacto en mundo, para reproyectar entre cuadros.
+    pub point: Vec<Vec3>,
+}
+
+impl Guides {
+    pub fn new(width: usize, height: usize) -> Self {
+        let mut guides = Guides::default();
+        guides.resize(width, height);
+        guides
+    }
+
+    /// Ajusta el tamaño y deja todos los píxeles como cielo.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.width = width;
+        self.height = height;
+        self.normal = vec![Vec3::zeros(); width * height];
+        self.albedo = vec![Vec3::zeros(); width * height];
+        self.depth = vec![f32::INFINITY; width * height];
+        self.point = vec![Vec3::zeros(); width * height];
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, intersect: &Intersect) {
+        let index = y * self.width + x;
+        if !intersect.is_intersecting {
+            self.normal[index] = Vec3::zeros();
+            self.albedo[index] = Vec3::zeros();
+            self.depth[index] = f32::INFINITY;
+            self.point[index] = Vec3::zeros();
+            return;
+        }
+        let [r, g, b] = intersect.surface_color();
+        self.normal[index] = intersect.normal;
+        self.albedo[index] = Vec3::new(r as f32, g as f32, b as f32) / 255.0;
+        self.depth[index] = intersect.distance;
+        self.point[index] = intersect.point;
+    }
+}
+
+/// Filtro À-Trous: varias pasadas de un núcleo B3 de 5x5 con huecos que
+/// doblan en cada iteración, ponderadas por color, normal, albedo y
+/// profundidad (Dammertz et al. 2010).
+#[derive(Debug, Clone)]
+pub struct Denoiser {
+    pub enabled: bool,
+    pub iterations: u32,
+    // Diferencia de color (0 a 1) que aún se mezcla; se reduce a la mitad
+    // en cada iteración para conservar detalle fino.
+    pub color_sigma: f32,
+    // Exponente sobre el coseno entre normales.
+    pub normal_power: f32,
+    pub albedo_sigma: f32,
+    // Diferencia de profundidad relativa a la del píxel central.
+    pub depth_sigma: f32,
+}
+
+impl Default for Denoiser {
+    fn default() -> Self {
+        Denoiser {
+            enabled: false,
+            iterations: 4,
+            color_sigma: 0.6,
+            normal_power: 64.0,
+            albedo_sigma: 0.1,
+            depth_sigma: 0.05,
+        }
+    }
+}
+
+const KERNEL: [f32; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+
+impl Denoiser {
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    /// Filtra el framebuffer en su sitio; `guides` debe ser del mismo tamaño.
+    pub fn apply(&self, framebuffer: &mut Framebuffer, guides: &Guides) {
+        let (width, height) = (framebuffer.width, framebuffer.height);
+        if guides.width != width || guides.height != height {
+            return;
+        }
+
+        let mut color: Vec<Vec3> = framebuffer.buffer.iter().map(|c| Color::from_hex(*c).to_vec3()).collect();
+        let mut filtered = color.clone();
+        let inv_albedo = 1.0 / (self.albedo_sigma * self.albedo_sigma);
+
+        for iteration in 0..self.iterations {
+            let step = 1isize << iteration;
+            let sigma = self.color_sigma * 0.5f32.powi(iteration as i32);
+            let inv_color = 1.0 / (sigma * sigma).max(1e-8);
+
+            for y in 0..height {
+                for x in 0..width {
+                    let center = y * width + x;
+                    // El cielo no tiene guías; se deja como está
+                    if !guides.depth[center].is_finite() {
+                        filtered[center] = color[center];
+                        continue;
+                    }
+
+                    let mut sum = Vec3::zeros();
+                    let mut weight_sum = 0.0;
+                    for (ky, wy) in KERNEL.iter().enumerate() {
+                        let sy = y as isize + (ky as isize - 2) * step;
+                        if sy < 0 || sy >= height as isize {
+                            continue;
+                        }
+                        for (kx, wx) in KERNEL.iter().enumerate() {
+                            let sx = x as isize + (kx as isize - 2) * step;
+                            if sx < 0 || sx >= width as isize {
+                                continue;
+                            }
+                            let sample = sy as usize * width + sx as usize;
+                            if !guides.depth[sample].is_finite() {
+                                continue;
+                            }
+
+                            let color_distance = (color[sample] - color[center]).norm_squared();
+                            let albedo_distance = (guides.albedo[sample] - guides.albedo[center]).norm_squared();
+                            let depth_distance = (guides.depth[sample] - guides.depth[center]).abs()
+                                / (self.depth_sigma * guides.depth[center]).max(1e-4);
+                            let normal_weight = guides.normal[sample].dot(&guides.normal[center]).max(0.0).powf(self.normal_power);
+
+                            let weight = wx * wy
+                                * normal_weight
+                                * (-color_distance * inv_color - albedo_distance * inv_albedo - depth_distance).exp();
+                            sum += color[sample] * weight;
+                            weight_sum += weight;
+                        }
+                    }
+                    filtered[center] = if weight_sum > 0.0 { sum / weight_sum } else { color[center] };
+                }
+            }
+            std::mem::swap(&mut color, &mut filtered);
+        }
+
+        for (pixel, value) in framebuffer.buffer.iter_mut().zip(&color) {
+            *pixel = Color::from_vec3(value).to_hex();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: usize = 16;
+
+    // Superficie plana a distancia 5 con la misma normal y albedo en todo el cuadro.
+    fn flat_guides() -> Guides {
+        let mut guides = Guides::new(SIZE, SIZE);
+        guides.normal.fill(Vec3::new(0.0, 1.0, 0.0));
+        guides.albedo.fill(Vec3::repeat(0.5));
+        guides.depth.fill(5.0);
+        guides
+    }
+
+    fn framebuffer(pixel: impl Fn(usize, usize) -> u32) -> Framebuffer {
+        let mut framebuffer = Framebuffer::new(SIZE, SIZE);
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                framebuffer.buffer[y * SIZE + x] = pixel(x, y);
+            }
+        }
+        framebuffer
+    }
+
+    // Ruido de tablero de ajedrez alrededor de `base`, en gris.
+    fn noisy(base: u32, x: usize, y: usize) -> u32 {
+        let v = if (x + y).is_multiple_of(2) { base + 12 } else { base - 12 };
+        (v << 16) | (v << 8) | v
+    }
+
+    fn red(pixel: u32) -> u32 {
+        pixel >> 16 & 0xFF
+    }
+
+    #[test]
+    fn flat_color_is_unchanged() {
+        let mut image = framebuffer(|_, _| 0x80_40_20);
+        Denoiser::default().apply(&mut image, &flat_guides());
+        assert!(image.buffer.iter().all(|&p| p == 0x80_40_20));
+    }
+
+    #[test]
+    fn sky_pixels_pass_through() {
+        let mut guides = flat_guides();
+        for y in 0..SIZE {
+            for x in 0..SIZE / 2 {
+                guides.depth[y * SIZE + x] = f32::INFINITY;
+            }
+        }
+        let original = framebuffer(|x, y| noisy(128, x, y));
+        let mut image = framebuffer(|x, y| noisy(128, x, y));
+        Denoiser::default().apply(&mut image, &guides);
+
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                let index = y * SIZE + x;
+                if x < SIZE / 2 {
+                    assert_eq!(image.buffer[index], original.buffer[index]);
+                } else {
+                    // La superficie sí se suaviza
+                    assert!(red(image.buffer[index]).abs_diff(128) < 8);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hard_normal_edges_are_not_blurred() {
+        // Dos caras en ángulo recto: oscura a la izquierda y clara a la derecha
+        let mut guides = flat_guides();
+        for y in 0..SIZE {
+            for x in 0..SIZE / 2 {
+                guides.normal[y * SIZE + x] = Vec3::new(1.0, 0.0, 0.0);
+            }
+        }
+        let pixel = |x: usize, y: usize| if x < SIZE / 2 { noisy(100, x, y) } else { noisy(150, x, y) };
+        let mut image = framebuffer(pixel);
+        Denoiser::default().apply(&mut image, &guides);
+
+        for y in 0..SIZE {
+            assert!(red(image.buffer[y * SIZE + SIZE / 2 - 1]).abs_diff(100) <= 12);
+            assert!(red(image.buffer[y * SIZE + SIZE / 2]).abs_diff(150) <= 12);
+        }
+
+        // Con las mismas normales a ambos lados el borde sí se mezcla
+        let mut image = framebuffer(pixel);
+        Denoiser::default().apply(&mut image, &flat_guides());
+        assert!((0..SIZE).any(|y| red(image.buffer[y * SIZE + SIZE / 2 - 1]).abs_diff(100) > 12));
+    }
+}
@@ -0,0 +1,133 @@
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+
+use crate::light::Light;
+use crate::math::smoothstep;
+
+/// Niebla homogénea más una capa exponencial por altura, con dispersión
+/// simple hacia las luces (rayos de luz entre los objetos).
+#[derive(Debug, Clone)]
+pub struct Fog {
+    pub enabled: bool,
+    // Albedo de la niebla; se multiplica por la luz ambiente del cielo.
+    pub color: Vec3,
+    pub density: f32,
+    pub height_density: f32,
+    pub height_falloff: f32,
+    pub base_height: f32,
+    // Intensidad de la dispersión hacia las luces; 0 desactiva el ray marching.
+    pub scattering: f32,
+    // Parámetro g de Henyey-Greenstein: > 0 dispersa hacia delante.
+    pub anisotropy: f32,
+    pub steps: u32,
+    // Pasos entre pruebas de sombra durante el ray marching; entre una y
+    // otra se reutiliza la visibilidad, que es lo más caro de cada paso.
+    pub shadow_stride: u32,
+    // Distancia usada para los rayos que llegan al cielo.
+    pub max_distance: f32,
+}
+
+impl Default for Fog {
+    fn default() -> Self {
+        Fog {
+            enabled: true,
+            color: Vec3::new(0.9, 0.92, 0.95),
+            density: 0.004,
+            height_density: 0.08,
+            height_falloff: 0.6,
+            base_height: 0.6,
+            scattering: 0.35,
+            anisotropy: 0.6,
+            steps: 8,
+            shadow_stride: 4,
+            max_distance: 60.0,
+        }
+    }
+}
+
+/// Factor de neblina matinal: máximo poco después del amanecer, nulo por la tarde.
+pub fn morning_mist(time_of_day: f32) -> f32 {
+    smoothstep(0.15, 0.26, time_of_day) * (1.0 - smoothstep(0.3, 0.42, time_of_day))
+}
+
+impl Fog {
+    pub fn density_at(&self, point: &Vec3) -> f32 {
+        self.density + self.height_density * (-self.height_falloff * (point.y - self.base_height)).exp()
+    }
+
+    /// Integral de la densidad a lo largo del segmento (analítica).
+    pub fn optical_depth(&self, origin: &Vec3, direction: &Vec3, distance: f32) -> f32 {
+        let homogeneous = self.density * distance;
+        let start = self.height_density * (-self.height_falloff * (origin.y - self.base_height)).exp();
+        let k = self.height_falloff * direction.y;
+        let height = if k.abs() > 1e-4 {
+            start * (1.0 - (-k * distance).exp()) / k
+        } else {
+            start * distance
+        };
+        homogeneous + height
+    }
+
+    pub fn transmittance(&self, origin: &Vec3, direction: &Vec3, distance: f32) -> f32 {
+        (-self.optical_depth(origin, direction, distance)).exp()
+    }
+
+    fn phase(&self, cos_theta: f32) -> f32 {
+        let g = self.anisotropy;
+        let denom = 1.0 + g * g - 2.0 * g * cos_theta;
+        (1.0 - g * g) / (4.0 * PI * denom.max(1e-4).powf(1.5))
+    }
+
+    /// Luz dispersada hacia la cámara a lo largo del rayo. `visibility` indica
+    /// si un punto ve la luz (0 en sombra, 1 iluminado).
+    pub fn in_scattering<F>(&self, origin: &Vec3, direction: &Vec3, distance: f32, lights: &[Light], visibility: F) -> Vec3
+    where
+        F: Fn(&Vec3, &Light) -> f32,
+    {
+        if self.scattering <= 0.0 || self.steps == 0 {
+            return Vec3::zeros();
+        }
+
+        let distance = distance.min(self.max_distance);
+        let step = distance / self.steps as f32;
+        let mut transmittance = 1.0;
+        let mut scattered = Vec3::zeros();
+        // Desplazamiento por rayo para cambiar bandas por ruido fino
+        let jitter = (direction.dot(&Vec3::new(12.9898, 78.233, 37.719)).sin() * 43758.547).fract().abs();
+        let shadow_stride = self.shadow_stride.max(1);
+        let mut visibilities = vec![0.0; lights.len()];
+
+        for i in 0..self.steps {
+            let point = origin + direction * ((i as f32 + jitter) * step);
+            let sigma = self.density_at(&point);
+
+            for (light, visible) in lights.iter().zip(visibilities.iter_mut()) {
+                if light.intensity <= 0.0 {
+                    continue;
+                }
+                if i % shadow_stride == 0 {
+                    *visible = visibility(&point, light);
+                }
+                let visible = *visible;
+                let to_light = (light.position - point).normalize();
+                if visible <= 0.0 {
+                    continue;
+                }
+                let light_color = Vec3::new(light.color[0] as f32, light.color[1] as f32, light.color[2] as f32) / 255.0;
+                let phase = self.phase(direction.dot(&to_light));
+                scattered += light_color * (light.intensity * visible * phase * sigma * transmittance * step);
+            }
+
+            transmittance *= (-sigma * step).exp();
+        }
+
+        scattered * self.scattering * 4.0 * PI
+    }
+
+    /// Atenúa `color` por la niebla y añade la luz ambiente dispersada.
+    pub fn apply(&self, color: &Vec3, origin: &Vec3, direction: &Vec3, distance: f32, ambient: &Vec3) -> Vec3 {
+        let distance = distance.min(self.max_distance);
+        let transmittance = self.transmittance(origin, direction, distance);
+        color * transmittance + self.color.component_mul(ambient) * (1.0 - transmittance)
+    }
+}
@@ -1,3 +1,7 @@
+use nalgebra_glm::Vec3;
+
+use crate::color::Color;
+
 pub struct Framebuffer {
     pub width: usize,
     pub height: usize,
@@ -36,4 +40,117 @@ impl Framebuffer {
     pub fn set_current_color(&mut self, color: u32) {
         self.current_color = color;
     }
+
+    /// Mezcla el color actual sobre el píxel con opacidad `alpha` (0 a 1).
+    pub fn blend_point(&mut self, x: usize, y: usize, alpha: f32) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let index = y * self.width + x;
+        let alpha = alpha.clamp(0.0, 1.0);
+        let src = Color::from_hex(self.current_color).to_vec3();
+        let dst = Color::from_hex(self.buffer[index]).to_vec3();
+        self.buffer[index] = Color::from_vec3(&(dst + (src - dst) * alpha)).to_hex();
+    }
+
+    /// Contorno de un rectángulo de `width` x `height` píxeles.
+    pub fn rect(&mut self, x: usize, y: usize, width: usize, height: usize) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        let right = x + width - 1;
+        let bottom = y + height - 1;
+        for px in x..=right {
+            self.point(px, y);
+            self.point(px, bottom);
+        }
+        for py in y..=bottom {
+            self.point(x, py);
+            self.point(right, py);
+        }
+    }
+
+    pub fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize) {
+        let x_end = (x + width).min(self.width);
+        let y_end = (y + height).min(self.height);
+        for py in y.min(y_end)..y_end {
+            let row = py * self.width;
+            for pixel in &mut self.buffer[row + x.min(x_end)..row + x_end] {
+                *pixel = self.current_color;
+            }
+        }
+    }
+
+    /// Rectángulo relleno y semitransparente, para fondos de texto.
+    pub fn blend_rect(&mut self, x: usize, y: usize, width: usize, height: usize, alpha: f32) {
+        for py in y..(y + height).min(self.height) {
+            for px in x..(x + width).min(self.width) {
+                self.blend_point(px, py, alpha);
+            }
+        }
+    }
+}
+
+/// Filtro para ampliar un framebuffer a otro de mayor resolución.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpscaleFilter {
+    Bilinear,
+    // Bilineal salvo en bordes marcados, donde toma el vecino más cercano
+    // para no emborronar las siluetas.
+    EdgeAware,
+}
+
+fn luminance(color: u32) -> f32 {
+    let rgb = Color::from_hex(color).to_vec3();
+    0.299 * rgb.x + 0.587 * rgb.y + 0.114 * rgb.z
+}
+
+fn is_edge(samples: &[(u32, f32)], threshold: f32) -> bool {
+    let (lo, hi) = samples.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), (c, _)| {
+        let l = luminance(*c);
+        (lo.min(l), hi.max(l))
+    });
+    hi - lo > threshold
+}
+
+impl Framebuffer {
+    /// Escala este framebuffer al tamaño de `target`.
+    pub fn upscale_into(&self, target: &mut Framebuffer, filter: UpscaleFilter) {
+        // Diferencia de luminancia a partir de la cual se considera un borde
+        const EDGE_THRESHOLD: f32 = 48.0 / 255.0;
+
+        let scale_x = self.width as f32 / target.width as f32;
+        let scale_y = self.height as f32 / target.height as f32;
+        let max_x = self.width - 1;
+        let max_y = self.height - 1;
+
+        for y in 0..target.height {
+            let sy = ((y as f32 + 0.5) * scale_y - 0.5).max(0.0);
+            let y0 = (sy as usize).min(max_y);
+            let y1 = (y0 + 1).min(max_y);
+            let fy = sy - y0 as f32;
+
+            for x in 0..target.width {
+                let sx = ((x as f32 + 0.5) * scale_x - 0.5).max(0.0);
+                let x0 = (sx as usize).min(max_x);
+                let x1 = (x0 + 1).min(max_x);
+                let fx = sx - x0 as f32;
+
+                let samples = [
+                    (self.buffer[y0 * self.width + x0], (1.0 - fx) * (1.0 - fy)),
+                    (self.buffer[y0 * self.width + x1], fx * (1.0 - fy)),
+                    (self.buffer[y1 * self.width + x0], (1.0 - fx) * fy),
+                    (self.buffer[y1 * self.width + x1], fx * fy),
+                ];
+
+                let color = if filter == UpscaleFilter::EdgeAware && is_edge(&samples, EDGE_THRESHOLD) {
+                    samples.iter().max_by(|a, b| a.1.total_cmp(&b.1)).map_or(0, |s| s.0)
+                } else {
+                    let sum: Vec3 = samples.iter().map(|(c, w)| Color::from_hex(*c).to_vec3() * *w).sum();
+                    Color::from_vec3(&sum).to_hex()
+                };
+                target.buffer[y * target.width + x] = color;
+            }
+        }
+    }
 }
@@ -0,0 +1,190 @@
+use std::fmt;
+use nalgebra_glm::Vec3;
+
+use crate::transform::Transform;
+
+// Por debajo de este tamaño una caja se considera plana.
+pub const MIN_EXTENT: f32 = 1e-4;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeometryError {
+    NonFinite { min: Vec3, max: Vec3 },
+    Degenerate { min: Vec3, max: Vec3 },
+}
+
+impl fmt::Display for GeometryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GeometryError::NonFinite { min, max } => write!(
+                f,
+                "non-finite bounds ({}, {}, {}) - ({}, {}, {})",
+                min.x, min.y, min.z, max.x, max.y, max.z
+            ),
+            GeometryError::Degenerate { min, max } => write!(
+                f,
+                "zero-volume bounds ({}, {}, {}) - ({}, {}, {})",
+                min.x, min.y, min.z, max.x, max.y, max.z
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GeometryError {}
+
+/// Caja alineada a los ejes con `min <= max` en cada componente.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    /// Acepta las esquinas en cualquier orden.
+    pub fn new(a: Vec3, b: Vec3) -> Self {
+        Aabb { min: a.inf(&b), max: a.sup(&b) }
+    }
+
+    pub fn size(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    pub fn volume(&self) -> f32 {
+        let size = self.size();
+        size.x * size.y * size.z
+    }
+
+    pub fn is_degenerate(&self) -> bool {
+        self.size().min() < MIN_EXTENT
+    }
+
+    pub fn is_finite(&self) -> bool {
+        self.min.iter().chain(self.max.iter()).all(|c| c.is_finite())
+    }
+
+    /// Intervalo de t en que el rayo está dentro de la caja, si la cruza
+    /// por delante del origen.
+    pub fn ray_range(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<(f32, f32)> {
+        let mut t_min: f32 = 0.0;
+        let mut t_max = f32::INFINITY;
+        for axis in 0..3 {
+            let inv = 1.0 / ray_direction[axis];
+            let mut t0 = (self.min[axis] - ray_origin[axis]) * inv;
+            let mut t1 = (self.max[axis] - ray_origin[axis]) * inv;
+            if t0 > t1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            t_min = t_min.max(t0);
+            t_max = t_max.min(t1);
+        }
+        (t_min <= t_max).then_some((t_min, t_max))
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb { min: self.min.inf(&other.min), max: self.max.sup(&other.max) }
+    }
+
+    /// Volumen compartido; las cajas que solo se tocan dan 0.
+    pub fn overlap_volume(&self, other: &Aabb) -> f32 {
+        let size = self.max.inf(&other.max) - self.min.sup(&other.min);
+        if size.min() <= 0.0 {
+            0.0
+        } else {
+            size.x * size.y * size.z
+        }
+    }
+
+    /// Caja que contiene las ocho esquinas transformadas.
+    pub fn transformed(&self, transform: &Transform) -> Aabb {
+        let mut result: Option<Aabb> = None;
+        for i in 0..8 {
+            let corner = Vec3::new(
+                if i & 1 == 0 { self.min.x } else { self.max.x },
+                if i & 2 == 0 { self.min.y } else { self.max.y },
+                if i & 4 == 0 { self.min.z } else { self.max.z },
+            );
+            let point = transform.transform_point(&corner);
+            let point_box = Aabb { min: point, max: point };
+            result = Some(result.map_or(point_box, |b| b.union(&point_box)));
+        }
+        result.unwrap_or(*self)
+    }
+}
+
+/// Problema encontrado al validar una escena; `name` es la ruta del nodo.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeometryIssue {
+    NonFinite { name: String },
+    ZeroVolume { name: String },
+    Overlap { first: String, second: String, volume: f32 },
+}
+
+impl fmt::Display for GeometryIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GeometryIssue::NonFinite { name } => write!(f, "{}: non-finite bounds", name),
+            GeometryIssue::ZeroVolume { name } => write!(f, "{}: zero volume", name),
+            GeometryIssue::Overlap { first, second, volume } => {
+                write!(f, "{} overlaps {} (volume {:.3})", first, second, volume)
+            }
+        }
+    }
+}
+
+/// Revisa una lista de cajas con nombre: sin volumen, no finitas o solapadas.
+pub fn validate(objects: &[(String, Aabb)]) -> Vec<GeometryIssue> {
+    let mut issues = Vec::new();
+    for (name, bounds) in objects {
+        if !bounds.is_finite() {
+            issues.push(GeometryIssue::NonFinite { name: name.clone() });
+        } else if bounds.is_degenerate() {
+            issues.push(GeometryIssue::ZeroVolume { name: name.clone() });
+        }
+    }
+    for (i, (first, a)) in objects.iter().enumerate() {
+        for (second, b) in &objects[i + 1..] {
+            let volume = a.overlap_volume(b);
+            if volume > MIN_EXTENT * MIN_EXTENT * MIN_EXTENT {
+                issues.push(GeometryIssue::Overlap { first: first.clone(), second: second.clone(), volume });
+            }
+        }
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_box() -> Aabb {
+        Aabb::new(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0))
+    }
+
+    fn assert_range(range: Option<(f32, f32)>, expected: (f32, f32)) {
+        let (t0, t1) = range.expect("the ray should cross the box");
+        assert!((t0 - expected.0).abs() < 1e-5 && (t1 - expected.1).abs() < 1e-5, "{:?}", (t0, t1));
+    }
+
+    #[test]
+    fn ray_range_through_the_box() {
+        let range = unit_box().ray_range(&Vec3::new(-5.0, 0.0, 0.0), &Vec3::new(1.0, 0.0, 0.0));
+        assert_range(range, (4.0, 6.0));
+        let diagonal = Vec3::new(1.0, 1.0, 0.0).normalize();
+        let range = unit_box().ray_range(&Vec3::new(-3.0, -3.0, 0.0), &diagonal);
+        assert_range(range, (2.0 * 2f32.sqrt(), 4.0 * 2f32.sqrt()));
+    }
+
+    #[test]
+    fn ray_range_starts_at_the_origin_inside_the_box() {
+        let range = unit_box().ray_range(&Vec3::zeros(), &Vec3::new(0.0, 0.0, -1.0));
+        assert_range(range, (0.0, 1.0));
+    }
+
+    #[test]
+    fn ray_range_misses() {
+        let bounds = unit_box();
+        // Pasa al lado, apunta en sentido contrario o la caja queda detrás
+        assert_eq!(bounds.ray_range(&Vec3::new(-5.0, 2.0, 0.0), &Vec3::new(1.0, 0.0, 0.0)), None);
+        assert_eq!(bounds.ray_range(&Vec3::new(-5.0, 0.0, 0.0), &Vec3::new(-1.0, 0.0, 0.0)), None);
+        assert_eq!(bounds.ray_range(&Vec3::new(5.0, 0.0, 0.0), &Vec3::new(1.0, 0.0, 0.0)), None);
+    }
+}
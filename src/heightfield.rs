@@ -0,0 +1,408 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use image::ImageError;
+use nalgebra_glm::Vec3;
+
+use crate::geometry::Aabb;
+use crate::material::Material;
+use crate::math::smoothstep;
+use crate::noise;
+use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::texture::Texture;
+
+#[derive(Debug)]
+pub enum HeightfieldError {
+    Image { path: PathBuf, source: ImageError },
+    TooSmall { columns: usize, rows: usize },
+}
+
+impl fmt::Display for HeightfieldError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HeightfieldError::Image { path, source } => {
+                write!(f, "failed to load heightmap {}: {}", path.display(), source)
+            }
+            HeightfieldError::TooSmall { columns, rows } => {
+                write!(f, "heightmap must be at least 2x2 samples, got {}x{}", columns, rows)
+            }
+        }
+    }
+}
+
+impl std::error::Error for HeightfieldError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            HeightfieldError::Image { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Mezcla de texturas según altura y pendiente: piedra en lo empinado,
+/// nieve por encima de `snow_line` y tierra en el resto.
+#[derive(Debug, Clone)]
+pub struct Splat {
+    pub snow: Rc<Texture>,
+    pub dirt: Rc<Texture>,
+    pub stone: Rc<Texture>,
+    // Altura en el mundo a partir de la cual hay nieve.
+    pub snow_line: f32,
+    // Ancho de la transición entre capas, en unidades del mundo.
+    pub blend: f32,
+    // Componente y de la normal por debajo de la cual domina la piedra.
+    pub steep_slope: f32,
+    // Repeticiones de textura por unidad.
+    pub tile: f32,
+}
+
+impl Splat {
+    pub fn new(snow: Rc<Texture>, dirt: Rc<Texture>, stone: Rc<Texture>) -> Self {
+        Splat { snow, dirt, stone, snow_line: 2.0, blend: 1.5, steep_slope: 0.75, tile: 0.5 }
+    }
+
+    pub fn with_snow_line(mut self, snow_line: f32, blend: f32) -> Self {
+        self.snow_line = snow_line;
+        self.blend = blend;
+        self
+    }
+
+    pub fn with_steep_slope(mut self, steep_slope: f32) -> Self {
+        self.steep_slope = steep_slope;
+        self
+    }
+
+    pub fn sample(&self, point: &Vec3, normal: &Vec3) -> [u8; 3] {
+        let p = point * self.tile;
+        let rock = 1.0 - smoothstep(self.steep_slope - 0.08, self.steep_slope + 0.08, normal.y);
+        // Borde de la nieve irregular en lugar de una línea recta
+        let snow_line = self.snow_line + noise::perlin(&(point * 0.3), 11) * self.blend;
+        let snow = smoothstep(snow_line - self.blend, snow_line + self.blend, point.y) * (1.0 - rock);
+        let dirt = (1.0 - rock - snow).max(0.0);
+
+        // La piedra se proyecta sobre la pared vertical dominante
+        let stone_uv = if normal.x.abs() > normal.z.abs() { (p.z, p.y) } else { (p.x, p.y) };
+        let samples = [
+            (snow, self.snow.get_color_wrapped(p.x, p.z)),
+            (dirt, self.dirt.get_color_wrapped(p.x, p.z)),
+            (rock, self.stone.get_color_wrapped(stone_uv.0, stone_uv.1)),
+        ];
+
+        let mut color = [0.0f32; 3];
+        for (weight, sample) in samples {
+            for channel in 0..3 {
+                color[channel] += sample[channel] as f32 * weight;
+            }
+        }
+        [color[0] as u8, color[1] as u8, color[2] as u8]
+    }
+}
+
+/// Terreno a partir de una rejilla de alturas, formado por dos triángulos por
+/// celda. Un árbol cuaternario de mínimos y máximos permite descartar zonas
+/// enteras que el rayo pasa por encima.
+#[derive(Debug, Clone)]
+pub struct Heightfield {
+    pub origin: Vec3,
+    pub cell_size: (f32, f32),
+    columns: usize,
+    rows: usize,
+    heights: Vec<f32>,
+    normals: Vec<Vec3>,
+    // levels[0] tiene una entrada por celda; cada nivel agrupa 2x2 del anterior.
+    levels: Vec<MinMaxLevel>,
+    pub splat: Option<Splat>,
+    pub color: [u8; 3],
+}
+
+#[derive(Debug, Clone)]
+struct MinMaxLevel {
+    columns: usize,
+    rows: usize,
+    ranges: Vec<(f32, f32)>,
+}
+
+impl Heightfield {
+    /// `height(x, z)` recibe coordenadas del mundo y devuelve la altura sobre
+    /// `origin.y`. `samples` es el número de muestras en x y en z.
+    pub fn from_fn<F>(samples: [usize; 2], origin: Vec3, size: (f32, f32), height: F) -> Result<Self, HeightfieldError>
+    where
+        F: Fn(f32, f32) -> f32,
+    {
+        let [columns, rows] = samples;
+        if columns < 2 || rows < 2 {
+            return Err(HeightfieldError::TooSmall { columns, rows });
+        }
+        let cell_size = (size.0 / (columns - 1) as f32, size.1 / (rows - 1) as f32);
+        let mut heights = Vec::with_capacity(columns * rows);
+        for j in 0..rows {
+            for i in 0..columns {
+                let x = origin.x + i as f32 * cell_size.0;
+                let z = origin.z + j as f32 * cell_size.1;
+                heights.push(origin.y + height(x, z));
+            }
+        }
+        Ok(Heightfield::build(origin, cell_size, columns, rows, heights))
+    }
+
+    /// Imagen en escala de grises: negro es `origin.y` y blanco `origin.y + height_scale`.
+    pub fn from_image(path: impl AsRef<Path>, origin: Vec3, size: (f32, f32), height_scale: f32) -> Result<Self, HeightfieldError> {
+        let path = path.as_ref();
+        let image = image::open(path)
+            .map_err(|source| HeightfieldError::Image { path: path.to_path_buf(), source })?
+            .to_luma16();
+        let (columns, rows) = (image.width() as usize, image.height() as usize);
+        if columns < 2 || rows < 2 {
+            return Err(HeightfieldError::TooSmall { columns, rows });
+        }
+        let cell_size = (size.0 / (columns - 1) as f32, size.1 / (rows - 1) as f32);
+        let heights = image.pixels().map(|p| origin.y + p[0] as f32 / 65535.0 * height_scale).collect();
+        Ok(Heightfield::build(origin, cell_size, columns, rows, heights))
+    }
+
+    pub fn with_splat(mut self, splat: Splat) -> Self {
+        self.splat = Some(splat);
+        self
+    }
+
+    fn build(origin: Vec3, cell_size: (f32, f32), columns: usize, rows: usize, heights: Vec<f32>) -> Self {
+        let height = |i: usize, j: usize| heights[j * columns + i];
+
+        // Normales por vértice con diferencias centradas
+        let mut normals = Vec::with_capacity(columns * rows);
+        for j in 0..rows {
+            for i in 0..columns {
+                let (i0, i1) = (i.saturating_sub(1), (i + 1).min(columns - 1));
+                let (j0, j1) = (j.saturating_sub(1), (j + 1).min(rows - 1));
+                let dx = (height(i1, j) - height(i0, j)) / ((i1 - i0) as f32 * cell_size.0);
+                let dz = (height(i, j1) - height(i, j0)) / ((j1 - j0) as f32 * cell_size.1);
+                normals.push(Vec3::new(-dx, 1.0, -dz).normalize());
+            }
+        }
+
+        let mut ranges = Vec::with_capacity((columns - 1) * (rows - 1));
+        for j in 0..rows - 1 {
+            for i in 0..columns - 1 {
+                let corners = [height(i, j), height(i + 1, j), height(i, j + 1), height(i + 1, j + 1)];
+                let lo = corners.iter().copied().fold(f32::INFINITY, f32::min);
+                let hi = corners.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+                ranges.push((lo, hi));
+            }
+        }
+        let mut levels = vec![MinMaxLevel { columns: columns - 1, rows: rows - 1, ranges }];
+        while levels.last().is_some_and(|l| l.columns > 1 || l.rows > 1) {
+            let below = &levels[levels.len() - 1];
+            let (c, r) = (below.columns.div_ceil(2), below.rows.div_ceil(2));
+            let mut ranges = vec![(f32::INFINITY, f32::NEG_INFINITY); c * r];
+            for j in 0..below.rows {
+                for i in 0..below.columns {
+                    let (lo, hi) = below.ranges[j * below.columns + i];
+                    let range = &mut ranges[(j / 2) * c + i / 2];
+                    range.0 = range.0.min(lo);
+                    range.1 = range.1.max(hi);
+                }
+            }
+            levels.push(MinMaxLevel { columns: c, rows: r, ranges });
+        }
+
+        Heightfield {
+            origin,
+            cell_size,
+            columns,
+            rows,
+            heights,
+            normals,
+            levels,
+            splat: None,
+            color: [255, 255, 255],
+        }
+    }
+
+    fn vertex(&self, i: usize, j: usize) -> Vec3 {
+        Vec3::new(
+            self.origin.x + i as f32 * self.cell_size.0,
+            self.heights[j * self.columns + i],
+            self.origin.z + j as f32 * self.cell_size.1,
+        )
+    }
+
+    fn normal_at(&self, x: f32, z: f32) -> Vec3 {
+        let fx = ((x - self.origin.x) / self.cell_size.0).clamp(0.0, (self.columns - 1) as f32);
+        let fz = ((z - self.origin.z) / self.cell_size.1).clamp(0.0, (self.rows - 1) as f32);
+        let (i, j) = ((fx as usize).min(self.columns - 2), (fz as usize).min(self.rows - 2));
+        let (s, t) = (fx - i as f32, fz - j as f32);
+        let n = |i: usize, j: usize| self.normals[j * self.columns + i];
+        let top = n(i, j).lerp(&n(i + 1, j), s);
+        let bottom = n(i, j + 1).lerp(&n(i + 1, j + 1), s);
+        top.lerp(&bottom, t).normalize()
+    }
+
+    // Caja del nodo (i, j) del nivel `level` y su intervalo de t.
+    fn node_distances(&self, level: usize, i: usize, j: usize, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<(f32, f32)> {
+        let span = 1usize << level;
+        let cells = (self.columns - 1, self.rows - 1);
+        let (lo, hi) = self.levels[level].ranges[j * self.levels[level].columns + i];
+        let min = Vec3::new(
+            self.origin.x + (i * span) as f32 * self.cell_size.0,
+            lo,
+            self.origin.z + (j * span) as f32 * self.cell_size.1,
+        );
+        let max = Vec3::new(
+            self.origin.x + ((i + 1) * span).min(cells.0) as f32 * self.cell_size.0,
+            hi,
+            self.origin.z + ((j + 1) * span).min(cells.1) as f32 * self.cell_size.1,
+        );
+
+        let mut t_enter: f32 = 0.0;
+        let mut t_leave = f32::INFINITY;
+        for axis in 0..3 {
+            let inv = 1.0 / ray_direction[axis];
+            let mut t0 = (min[axis] - ray_origin[axis]) * inv;
+            let mut t1 = (max[axis] - ray_origin[axis]) * inv;
+            if t0 > t1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            // Rayo paralelo dentro de la losa: 0 * inf da NaN
+            if t0.is_nan() || t1.is_nan() {
+                continue;
+            }
+            t_enter = t_enter.max(t0);
+            t_leave = t_leave.min(t1);
+        }
+        (t_enter <= t_leave).then_some((t_enter, t_leave))
+    }
+
+    fn intersect_cell(&self, i: usize, j: usize, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<f32> {
+        let p00 = self.vertex(i, j);
+        let p10 = self.vertex(i + 1, j);
+        let p01 = self.vertex(i, j + 1);
+        let p11 = self.vertex(i + 1, j + 1);
+        let first = intersect_triangle(&p00, &p11, &p10, ray_origin, ray_direction);
+        let second = intersect_triangle(&p00, &p01, &p11, ray_origin, ray_direction);
+        match (first, second) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+// Möller-Trumbore; acepta ambos lados del triángulo.
+fn intersect_triangle(a: &Vec3, b: &Vec3, c: &Vec3, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<f32> {
+    let edge1 = b - a;
+    let edge2 = c - a;
+    let p = ray_direction.cross(&edge2);
+    let det = edge1.dot(&p);
+    if det.abs() < 1e-8 {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+    let s = ray_origin - a;
+    let u = s.dot(&p) * inv_det;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = s.cross(&edge1);
+    let v = ray_direction.dot(&q) * inv_det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    let t = edge2.dot(&q) * inv_det;
+    (t > 1e-4).then_some(t)
+}
+
+impl RayIntersect for Heightfield {
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
+        let top = self.levels.len() - 1;
+        let mut best = f32::INFINITY;
+        let mut stack = vec![(top, 0usize, 0usize)];
+
+        while let Some((level, i, j)) = stack.pop() {
+            match self.node_distances(level, i, j, ray_origin, ray_direction) {
+                Some((t0, _)) if t0 < best => {}
+                _ => continue,
+            }
+            if level == 0 {
+                if let Some(t) = self.intersect_cell(i, j, ray_origin, ray_direction) {
+                    best = best.min(t);
+                }
+                continue;
+            }
+
+            // Hijos ordenados para visitar primero el más cercano
+            let below = &self.levels[level - 1];
+            let mut children: Vec<(f32, usize, usize)> = Vec::with_capacity(4);
+            for (ci, cj) in [(2 * i, 2 * j), (2 * i + 1, 2 * j), (2 * i, 2 * j + 1), (2 * i + 1, 2 * j + 1)] {
+                if ci < below.columns && cj < below.rows {
+                    if let Some((ct0, _)) = self.node_distances(level - 1, ci, cj, ray_origin, ray_direction) {
+                        children.push((ct0, ci, cj));
+                    }
+                }
+            }
+            children.sort_by(|a, b| b.0.total_cmp(&a.0));
+            stack.extend(children.into_iter().map(|(_, ci, cj)| (level - 1, ci, cj)));
+        }
+
+        if !best.is_finite() {
+            return Intersect::empty();
+        }
+
+        let point = ray_origin + ray_direction * best;
+        let normal = self.normal_at(point.x, point.z);
+        let color = match &self.splat {
+            Some(splat) => splat.sample(&point, &normal),
+            None => self.color,
+        };
+        let material = Material::new([0.9, 0.1, 0.0, 0.0], color, 10.0, 1.0, None);
+        let mut intersect = Intersect::new(point, normal, best, material, None, None);
+        intersect.local_point = point - self.origin;
+        intersect
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        let (lo, hi) = self.levels[self.levels.len() - 1].ranges[0];
+        let min = Vec3::new(self.origin.x, lo, self.origin.z);
+        let max = Vec3::new(
+            self.origin.x + (self.columns - 1) as f32 * self.cell_size.0,
+            hi,
+            self.origin.z + (self.rows - 1) as f32 * self.cell_size.1,
+        );
+        Some(Aabb::new(min, max))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_heightmap(name: &str, width: u32, height: u32, pixels: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("proyecto2-{}-{}.png", name, std::process::id()));
+        image::GrayImage::from_raw(width, height, pixels.to_vec()).unwrap().save(&path).unwrap();
+        path
+    }
+
+    #[test]
+    fn image_maps_black_to_origin_and_white_to_full_height() {
+        let path = write_heightmap("ramp", 3, 2, &[0, 255, 0, 0, 255, 0]);
+        let terrain = Heightfield::from_image(&path, Vec3::new(-1.0, 2.0, 0.0), (4.0, 2.0), 6.0);
+        std::fs::remove_file(&path).unwrap();
+        let terrain = terrain.unwrap();
+
+        assert_eq!((terrain.columns, terrain.rows), (3, 2));
+        assert_eq!(terrain.cell_size, (2.0, 2.0));
+        assert_eq!(terrain.heights, vec![2.0, 8.0, 2.0, 2.0, 8.0, 2.0]);
+    }
+
+    #[test]
+    fn image_errors() {
+        let missing = std::env::temp_dir().join("proyecto2-missing-heightmap.png");
+        assert!(matches!(
+            Heightfield::from_image(&missing, Vec3::zeros(), (1.0, 1.0), 1.0),
+            Err(HeightfieldError::Image { path, .. }) if path == missing
+        ));
+
+        let path = write_heightmap("line", 4, 1, &[0, 64, 128, 255]);
+        let terrain = Heightfield::from_image(&path, Vec3::zeros(), (1.0, 1.0), 1.0);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(terrain, Err(HeightfieldError::TooSmall { columns: 4, rows: 1 })));
+    }
+}
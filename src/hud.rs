@@ -0,0 +1,150 @@
+use nalgebra_glm::Vec3;
+
+use crate::debug_view::DebugMode;
+use crate::font;
+use crate::framebuffer::Framebuffer;
+
+const MARGIN: usize = 8;
+const PADDING: usize = 6;
+
+/// Datos de un cuadro que muestra el HUD.
+#[derive(Debug, Clone)]
+pub struct HudInfo {
+    pub fps: f32,
+    pub frame_ms: f32,
+    pub time_of_day: f32,
+    pub camera_eye: Vec3,
+    pub rays: u64,
+    pub low_res: bool,
+    pub paused: bool,
+    pub fog: bool,
+    pub recording: bool,
+    pub denoise: bool,
+    pub temporal: bool,
+    // Datos del objeto seleccionado con el ratón.
+    pub selection: Vec<String>,
+    pub mode: DebugMode,
+}
+
+/// Capa de texto sobre la imagen ya renderizada.
+#[derive(Debug, Clone)]
+pub struct Hud {
+    pub visible: bool,
+    pub scale: usize,
+    pub text_color: u32,
+    pub panel_color: u32,
+    pub panel_alpha: f32,
+    // Líneas de la lista de controles; vacía no se dibuja.
+    pub controls: Vec<String>,
+}
+
+impl Default for Hud {
+    fn default() -> Self {
+        Hud {
+            visible: true,
+            scale: 2,
+            text_color: 0xFFFFFF,
+            panel_color: 0x000000,
+            panel_alpha: 0.55,
+            controls: Vec::new(),
+        }
+    }
+}
+
+/// Hora del día (0 a 1) como "HH:MM".
+fn clock_time(time_of_day: f32) -> String {
+    let minutes = (time_of_day.rem_euclid(1.0) * 24.0 * 60.0) as u32 % (24 * 60);
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// Rayos con sufijo K o M para que quepan en una línea.
+fn ray_count(rays: u64) -> String {
+    match rays {
+        r if r >= 1_000_000 => format!("{:.2}M", r as f64 / 1_000_000.0),
+        r if r >= 1_000 => format!("{:.1}K", r as f64 / 1_000.0),
+        r => r.to_string(),
+    }
+}
+
+impl Hud {
+    pub fn with_controls(mut self, controls: Vec<String>) -> Self {
+        self.controls = controls;
+        self
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    pub fn draw(&self, framebuffer: &mut Framebuffer, info: &HudInfo) {
+        if !self.visible {
+            return;
+        }
+
+        let mut flags = Vec::new();
+        if info.mode != DebugMode::Shaded {
+            flags.push(info.mode.label());
+        }
+        if info.low_res {
+            flags.push("MEDIA RES");
+        }
+        if info.paused {
+            flags.push("PAUSA");
+        }
+        if info.fog {
+            flags.push("NIEBLA");
+        }
+        if info.recording {
+            flags.push("GRABANDO");
+        }
+        if info.denoise {
+            flags.push("FILTRO");
+        }
+        if info.temporal {
+            flags.push("ACUMULA");
+        }
+        let stats = format!(
+            "{:.1} FPS  {:.1} MS\nHORA {}\nCAMARA {:.2} {:.2} {:.2}\nRAYOS {}\n{}",
+            info.fps,
+            info.frame_ms,
+            clock_time(info.time_of_day),
+            info.camera_eye.x,
+            info.camera_eye.y,
+            info.camera_eye.z,
+            ray_count(info.rays),
+            flags.join("  "),
+        );
+        self.panel(framebuffer, MARGIN, MARGIN, &stats, self.scale);
+
+        if !info.selection.is_empty() {
+            let selection = info.selection.join("\n");
+            let scale = (self.scale / 2).max(1);
+            let (width, _) = font::text_size(&selection, scale);
+            let x = framebuffer.width.saturating_sub(MARGIN + width + 2 * PADDING);
+            self.panel(framebuffer, x, MARGIN, &selection, scale);
+        }
+
+        if self.controls.is_empty() {
+            return;
+        }
+        // La lista de controles va más pequeña para tapar menos la escena
+        let controls = self.controls.join("\n");
+        let controls_scale = (self.scale / 2).max(1);
+        let (_, height) = font::text_size(&controls, controls_scale);
+        let y = framebuffer.height.saturating_sub(MARGIN + height + 2 * PADDING);
+        self.panel(framebuffer, MARGIN, y, &controls, controls_scale);
+    }
+
+    // Fondo semitransparente con borde y el texto encima.
+    fn panel(&self, framebuffer: &mut Framebuffer, x: usize, y: usize, text: &str, scale: usize) {
+        let (width, height) = font::text_size(text, scale);
+        let panel_width = width + 2 * PADDING;
+        let panel_height = height + 2 * PADDING;
+
+        framebuffer.set_current_color(self.panel_color);
+        framebuffer.blend_rect(x, y, panel_width, panel_height, self.panel_alpha);
+        framebuffer.set_current_color(self.text_color);
+        framebuffer.rect(x, y, panel_width, panel_height);
+        font::draw_text(framebuffer, x + PADDING, y + PADDING, text, scale);
+    }
+}
@@ -0,0 +1,144 @@
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+
+use crate::sky::Sky;
+
+// Iluminación basada en imagen a partir del cielo: irradiancia difusa en
+// armónicos esféricos (orden 2) y reflejos especulares prefiltrados.
+
+const SAMPLE_COUNT: usize = 256;
+const SPECULAR_WIDTH: usize = 32;
+const SPECULAR_HEIGHT: usize = 16;
+// Exponentes de Phong de cada nivel prefiltrado, de más rugoso a más pulido.
+const SPECULAR_EXPONENTS: [f32; 3] = [4.0, 16.0, 64.0];
+
+fn fibonacci_directions(count: usize) -> Vec<Vec3> {
+    let golden = PI * (3.0 - 5.0f32.sqrt());
+    (0..count)
+        .map(|i| {
+            let y = 1.0 - 2.0 * (i as f32 + 0.5) / count as f32;
+            let r = (1.0 - y * y).sqrt();
+            let phi = golden * i as f32;
+            Vec3::new(r * phi.cos(), y, r * phi.sin())
+        })
+        .collect()
+}
+
+fn sh_basis(d: &Vec3) -> [f32; 9] {
+    [
+        0.282095,
+        0.488603 * d.y,
+        0.488603 * d.z,
+        0.488603 * d.x,
+        1.092548 * d.x * d.y,
+        1.092548 * d.y * d.z,
+        0.315392 * (3.0 * d.z * d.z - 1.0),
+        1.092548 * d.x * d.z,
+        0.546274 * (d.x * d.x - d.y * d.y),
+    ]
+}
+
+fn equirect_direction(x: usize, y: usize) -> Vec3 {
+    let phi = (x as f32 + 0.5) / SPECULAR_WIDTH as f32 * 2.0 * PI;
+    let theta = (y as f32 + 0.5) / SPECULAR_HEIGHT as f32 * PI;
+    Vec3::new(theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin())
+}
+
+pub struct EnvironmentLight {
+    sh: [Vec3; 9],
+    specular: Vec<Vec<Vec3>>,
+    pub intensity: f32,
+}
+
+impl Default for EnvironmentLight {
+    fn default() -> Self {
+        EnvironmentLight {
+            sh: [Vec3::zeros(); 9],
+            specular: vec![vec![Vec3::zeros(); SPECULAR_WIDTH * SPECULAR_HEIGHT]; SPECULAR_EXPONENTS.len()],
+            intensity: 1.0,
+        }
+    }
+}
+
+impl EnvironmentLight {
+    pub fn from_sky(sky: &Sky) -> Self {
+        let mut environment = EnvironmentLight::default();
+        environment.update(sky);
+        environment
+    }
+
+    /// Vuelve a proyectar el cielo; llamar cuando cambia la hora o el modo.
+    pub fn update(&mut self, sky: &Sky) {
+        let directions = fibonacci_directions(SAMPLE_COUNT);
+        let radiance: Vec<Vec3> = directions.iter().map(|d| sky.sample(d)).collect();
+
+        let weight = 4.0 * PI / SAMPLE_COUNT as f32;
+        let mut sh = [Vec3::zeros(); 9];
+        for (d, l) in directions.iter().zip(&radiance) {
+            for (coefficient, basis) in sh.iter_mut().zip(sh_basis(d)) {
+                *coefficient += l * basis * weight;
+            }
+        }
+        self.sh = sh;
+
+        for (level, exponent) in SPECULAR_EXPONENTS.iter().enumerate() {
+            for y in 0..SPECULAR_HEIGHT {
+                for x in 0..SPECULAR_WIDTH {
+                    let r = equirect_direction(x, y);
+                    let mut total = Vec3::zeros();
+                    let mut total_weight = 0.0;
+                    for (d, l) in directions.iter().zip(&radiance) {
+                        let cos = r.dot(d);
+                        if cos > 0.0 {
+                            let w = cos.powf(*exponent);
+                            total += l * w;
+                            total_weight += w;
+                        }
+                    }
+                    self.specular[level][y * SPECULAR_WIDTH + x] = if total_weight > 0.0 {
+                        total / total_weight
+                    } else {
+                        Vec3::zeros()
+                    };
+                }
+            }
+        }
+    }
+
+    /// Luz difusa que recibe una superficie blanca lambertiana con esa normal.
+    pub fn irradiance(&self, normal: &Vec3) -> Vec3 {
+        const BAND: [f32; 9] = [PI, 2.0 * PI / 3.0, 2.0 * PI / 3.0, 2.0 * PI / 3.0, PI / 4.0, PI / 4.0, PI / 4.0, PI / 4.0, PI / 4.0];
+        let mut irradiance = Vec3::zeros();
+        for ((coefficient, basis), band) in self.sh.iter().zip(sh_basis(normal)).zip(BAND) {
+            irradiance += coefficient * basis * band;
+        }
+        irradiance.map(|c| c.max(0.0)) / PI * self.intensity
+    }
+
+    /// Reflejo del entorno en la dirección `reflect_dir` para un exponente de Phong.
+    pub fn specular(&self, reflect_dir: &Vec3, exponent: f32) -> Vec3 {
+        let target = exponent.max(1.0).ln();
+        let level = SPECULAR_EXPONENTS
+            .iter()
+            .enumerate()
+            .min_by(|a, b| (a.1.ln() - target).abs().total_cmp(&(b.1.ln() - target).abs()))
+            .map(|(i, _)| i)
+            .unwrap_or(0);
+
+        let d = reflect_dir.normalize();
+        let u = d.z.atan2(d.x).rem_euclid(2.0 * PI) / (2.0 * PI);
+        let v = d.y.clamp(-1.0, 1.0).acos() / PI;
+        let fx = (u * SPECULAR_WIDTH as f32 - 0.5).rem_euclid(SPECULAR_WIDTH as f32);
+        let fy = (v * SPECULAR_HEIGHT as f32 - 0.5).clamp(0.0, (SPECULAR_HEIGHT - 1) as f32);
+        let x0 = (fx.floor() as usize).min(SPECULAR_WIDTH - 1);
+        let y0 = fy.floor() as usize;
+        let x1 = (x0 + 1) % SPECULAR_WIDTH;
+        let y1 = (y0 + 1).min(SPECULAR_HEIGHT - 1);
+
+        let map = &self.specular[level];
+        let texel = |x: usize, y: usize| map[y * SPECULAR_WIDTH + x];
+        let top = texel(x0, y0).lerp(&texel(x1, y0), fx.fract());
+        let bottom = texel(x0, y1).lerp(&texel(x1, y1), fx.fract());
+        top.lerp(&bottom, fy.fract()) * self.intensity
+    }
+}
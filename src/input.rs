@@ -0,0 +1,498 @@
+use std::path::Path;
+use minifb::{Key, KeyRepeat, MouseButton, MouseMode, Window};
+
+use crate::config::{self, ConfigEntry, ConfigError};
+use crate::debug_view::DebugMode;
+
+/// Acciones que se pueden asignar a teclas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    OrbitLeft,
+    OrbitRight,
+    OrbitUp,
+    OrbitDown,
+    ZoomIn,
+    ZoomOut,
+    Midnight,
+    Noon,
+    TimeForward,
+    TimeBackward,
+    ToggleSky,
+    ToggleFog,
+    ToggleResolution,
+    TogglePause,
+    ScrubBackward,
+    ScrubForward,
+    StepFrame,
+    ToggleHud,
+    Screenshot,
+    ToggleRecording,
+    ToggleRawRecording,
+    ToggleDenoise,
+    ToggleTemporal,
+    ModeShaded,
+    ModeNormals,
+    ModeUv,
+    ModeDepth,
+    ModeAlbedo,
+    ModeShadows,
+    ModeIds,
+    ModeHeatmap,
+    ModeReflections,
+}
+
+/// Cuándo se dispara una acción respecto a su tecla.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    // Cada cuadro mientras la tecla está abajo.
+    Hold,
+    // Solo el cuadro en que se pulsa.
+    Press,
+    // Al pulsar y luego con la repetición del teclado.
+    Repeat,
+}
+
+impl Action {
+    pub const ALL: [Action; 32] = [
+        Action::OrbitLeft,
+        Action::OrbitRight,
+        Action::OrbitUp,
+        Action::OrbitDown,
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::Midnight,
+        Action::Noon,
+        Action::TimeForward,
+        Action::TimeBackward,
+        Action::ToggleSky,
+        Action::ToggleFog,
+        Action::ToggleResolution,
+        Action::TogglePause,
+        Action::ScrubBackward,
+        Action::ScrubForward,
+        Action::StepFrame,
+        Action::ToggleHud,
+        Action::Screenshot,
+        Action::ToggleRecording,
+        Action::ToggleRawRecording,
+        Action::ToggleDenoise,
+        Action::ToggleTemporal,
+        Action::ModeShaded,
+        Action::ModeNormals,
+        Action::ModeUv,
+        Action::ModeDepth,
+        Action::ModeAlbedo,
+        Action::ModeShadows,
+        Action::ModeIds,
+        Action::ModeHeatmap,
+        Action::ModeReflections,
+    ];
+
+    /// Nombre en el archivo de configuración.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::OrbitLeft => "orbit_left",
+            Action::OrbitRight => "orbit_right",
+            Action::OrbitUp => "orbit_up",
+            Action::OrbitDown => "orbit_down",
+            Action::ZoomIn => "zoom_in",
+            Action::ZoomOut => "zoom_out",
+            Action::Midnight => "midnight",
+            Action::Noon => "noon",
+            Action::TimeForward => "time_forward",
+            Action::TimeBackward => "time_backward",
+            Action::ToggleSky => "toggle_sky",
+            Action::ToggleFog => "toggle_fog",
+            Action::ToggleResolution => "toggle_resolution",
+            Action::TogglePause => "toggle_pause",
+            Action::ScrubBackward => "scrub_backward",
+            Action::ScrubForward => "scrub_forward",
+            Action::StepFrame => "step_frame",
+            Action::ToggleHud => "toggle_hud",
+            Action::Screenshot => "screenshot",
+            Action::ToggleRecording => "toggle_recording",
+            Action::ToggleRawRecording => "toggle_raw_recording",
+            Action::ToggleDenoise => "toggle_denoise",
+            Action::ToggleTemporal => "toggle_temporal",
+            Action::ModeShaded => "mode_shaded",
+            Action::ModeNormals => "mode_normals",
+            Action::ModeUv => "mode_uv",
+            Action::ModeDepth => "mode_depth",
+            Action::ModeAlbedo => "mode_albedo",
+            Action::ModeShadows => "mode_shadows",
+            Action::ModeIds => "mode_ids",
+            Action::ModeHeatmap => "mode_heatmap",
+            Action::ModeReflections => "mode_reflections",
+        }
+    }
+
+    /// Texto para la lista de controles del HUD.
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::OrbitLeft => "ORBITAR IZQUIERDA",
+            Action::OrbitRight => "ORBITAR DERECHA",
+            Action::OrbitUp => "ORBITAR ARRIBA",
+            Action::OrbitDown => "ORBITAR ABAJO",
+            Action::ZoomIn => "ACERCAR",
+            Action::ZoomOut => "ALEJAR",
+            Action::Midnight => "MEDIANOCHE",
+            Action::Noon => "MEDIODIA",
+            Action::TimeForward => "HORA +",
+            Action::TimeBackward => "HORA -",
+            Action::ToggleSky => "CIELO",
+            Action::ToggleFog => "NIEBLA",
+            Action::ToggleResolution => "RESOLUCION DINAMICA",
+            Action::TogglePause => "PAUSA",
+            Action::ScrubBackward => "RETROCEDER",
+            Action::ScrubForward => "AVANZAR",
+            Action::StepFrame => "UN CUADRO",
+            Action::ToggleHud => "OCULTAR HUD",
+            Action::Screenshot => "CAPTURA PNG",
+            Action::ToggleRecording => "GRABAR PNG",
+            Action::ToggleRawRecording => "GRABAR EN CRUDO",
+            Action::ToggleDenoise => "FILTRAR RUIDO",
+            Action::ToggleTemporal => "ACUMULAR CUADROS",
+            Action::ModeShaded => "MODO NORMAL",
+            Action::ModeNormals => "MODO NORMALES",
+            Action::ModeUv => "MODO UV",
+            Action::ModeDepth => "MODO PROFUNDIDAD",
+            Action::ModeAlbedo => "MODO ALBEDO",
+            Action::ModeShadows => "MODO SOMBRAS",
+            Action::ModeIds => "MODO OBJETOS",
+            Action::ModeHeatmap => "MODO COSTE",
+            Action::ModeReflections => "MODO REBOTES",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|a| a.name() == name)
+    }
+
+    /// Modo de render que selecciona la acción, si es de ese tipo.
+    pub fn debug_mode(self) -> Option<DebugMode> {
+        match self {
+            Action::ModeShaded => Some(DebugMode::Shaded),
+            Action::ModeNormals => Some(DebugMode::Normals),
+            Action::ModeUv => Some(DebugMode::UvChecker),
+            Action::ModeDepth => Some(DebugMode::Depth),
+            Action::ModeAlbedo => Some(DebugMode::Albedo),
+            Action::ModeShadows => Some(DebugMode::ShadowMask),
+            Action::ModeIds => Some(DebugMode::ObjectId),
+            Action::ModeHeatmap => Some(DebugMode::Heatmap),
+            Action::ModeReflections => Some(DebugMode::ReflectionDepth),
+            _ => None,
+        }
+    }
+
+    /// Disparo que se usa si la configuración no indica otro.
+    pub fn default_trigger(self) -> Trigger {
+        match self {
+            Action::OrbitLeft
+            | Action::OrbitRight
+            | Action::OrbitUp
+            | Action::OrbitDown
+            | Action::ZoomIn
+            | Action::ZoomOut
+            | Action::Midnight
+            | Action::Noon
+            | Action::TimeForward
+            | Action::TimeBackward => Trigger::Hold,
+            Action::ScrubBackward | Action::ScrubForward | Action::StepFrame => Trigger::Repeat,
+            _ => Trigger::Press,
+        }
+    }
+}
+
+// Nombres de tecla aceptados en la configuración.
+const KEY_NAMES: &[(&str, Key)] = &[
+    ("a", Key::A), ("b", Key::B), ("c", Key::C), ("d", Key::D), ("e", Key::E), ("f", Key::F),
+    ("g", Key::G), ("h", Key::H), ("i", Key::I), ("j", Key::J), ("k", Key::K), ("l", Key::L),
+    ("m", Key::M), ("n", Key::N), ("o", Key::O), ("p", Key::P), ("q", Key::Q), ("r", Key::R),
+    ("s", Key::S), ("t", Key::T), ("u", Key::U), ("v", Key::V), ("w", Key::W), ("x", Key::X),
+    ("y", Key::Y), ("z", Key::Z),
+    ("0", Key::Key0), ("1", Key::Key1), ("2", Key::Key2), ("3", Key::Key3), ("4", Key::Key4),
+    ("5", Key::Key5), ("6", Key::Key6), ("7", Key::Key7), ("8", Key::Key8), ("9", Key::Key9),
+    ("f1", Key::F1), ("f2", Key::F2), ("f3", Key::F3), ("f4", Key::F4), ("f5", Key::F5),
+    ("f6", Key::F6), ("f7", Key::F7), ("f8", Key::F8), ("f9", Key::F9), ("f10", Key::F10),
+    ("f11", Key::F11), ("f12", Key::F12),
+    ("left", Key::Left), ("right", Key::Right), ("up", Key::Up), ("down", Key::Down),
+    ("space", Key::Space), ("enter", Key::Enter), ("tab", Key::Tab), ("backspace", Key::Backspace),
+    ("insert", Key::Insert), ("delete", Key::Delete), ("home", Key::Home), ("end", Key::End),
+    ("pageup", Key::PageUp), ("pagedown", Key::PageDown),
+    ("[", Key::LeftBracket), ("]", Key::RightBracket), (".", Key::Period), (",", Key::Comma),
+    ("-", Key::Minus), ("=", Key::Equal), ("/", Key::Slash), (";", Key::Semicolon),
+    ("'", Key::Apostrophe), ("`", Key::Backquote), ("\\", Key::Backslash),
+    ("shift", Key::LeftShift), ("rightshift", Key::RightShift),
+    ("ctrl", Key::LeftCtrl), ("rightctrl", Key::RightCtrl),
+    ("alt", Key::LeftAlt), ("rightalt", Key::RightAlt),
+];
+
+pub fn parse_key(name: &str) -> Option<Key> {
+    let name = name.to_ascii_lowercase();
+    KEY_NAMES.iter().find(|(n, _)| *n == name).map(|(_, k)| *k)
+}
+
+pub fn key_name(key: Key) -> &'static str {
+    KEY_NAMES.iter().find(|(_, k)| *k == key).map_or("?", |(n, _)| n)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Binding {
+    pub action: Action,
+    pub key: Key,
+    pub trigger: Trigger,
+}
+
+/// Teclas asignadas a cada acción y sensibilidad de la rueda del ratón.
+#[derive(Debug, Clone)]
+pub struct InputMap {
+    pub bindings: Vec<Binding>,
+    // Unidades de zoom por paso de rueda; 0 la desactiva.
+    pub wheel_zoom: f32,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        let defaults = [
+            (Action::OrbitLeft, Key::Left),
+            (Action::OrbitRight, Key::Right),
+            (Action::OrbitUp, Key::Up),
+            (Action::OrbitDown, Key::Down),
+            (Action::ZoomIn, Key::X),
+            (Action::ZoomOut, Key::Y),
+            (Action::Midnight, Key::N),
+            (Action::Noon, Key::D),
+            (Action::TimeForward, Key::W),
+            (Action::TimeBackward, Key::S),
+            (Action::ToggleSky, Key::M),
+            (Action::ToggleFog, Key::F),
+            (Action::ToggleResolution, Key::R),
+            (Action::TogglePause, Key::P),
+            (Action::ScrubBackward, Key::LeftBracket),
+            (Action::ScrubForward, Key::RightBracket),
+            (Action::StepFrame, Key::Period),
+            (Action::ToggleHud, Key::H),
+            (Action::Screenshot, Key::C),
+            (Action::ToggleRecording, Key::V),
+            (Action::ToggleRawRecording, Key::B),
+            (Action::ToggleDenoise, Key::J),
+            (Action::ToggleTemporal, Key::K),
+            (Action::ModeShaded, Key::Key1),
+            (Action::ModeNormals, Key::Key2),
+            (Action::ModeUv, Key::Key3),
+            (Action::ModeDepth, Key::Key4),
+            (Action::ModeAlbedo, Key::Key5),
+            (Action::ModeShadows, Key::Key6),
+            (Action::ModeIds, Key::Key7),
+            (Action::ModeHeatmap, Key::Key8),
+            (Action::ModeReflections, Key::Key9),
+        ];
+        InputMap {
+            bindings: defaults
+                .into_iter()
+                .map(|(action, key)| Binding { action, key, trigger: action.default_trigger() })
+                .collect(),
+            wheel_zoom: 0.5,
+        }
+    }
+}
+
+impl InputMap {
+    /// Carga las asignaciones desde un archivo; una acción que aparezca en él
+    /// sustituye todas sus teclas por defecto, las demás se conservan.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        InputMap::from_entries(config::load(path)?)
+    }
+
+    fn from_entries(entries: Vec<ConfigEntry>) -> Result<Self, ConfigError> {
+        let mut map = InputMap::default();
+        let mut configured: Vec<Action> = Vec::new();
+
+        for entry in entries {
+            match (entry.section.as_str(), entry.key.as_str()) {
+                ("mouse", "wheel_zoom") => map.wheel_zoom = entry.parse()?,
+                ("bindings", name) => {
+                    let action = Action::from_name(name).ok_or_else(|| entry.invalid())?;
+                    // "tecla" o "tecla hold|press|repeat"
+                    let mut parts = entry.value.split_whitespace();
+                    let key = parts.next().and_then(parse_key).ok_or_else(|| entry.invalid())?;
+                    let trigger = match parts.next() {
+                        None => action.default_trigger(),
+                        Some("hold") => Trigger::Hold,
+                        Some("press") => Trigger::Press,
+                        Some("repeat") => Trigger::Repeat,
+                        Some(_) => return Err(entry.invalid()),
+                    };
+                    if parts.next().is_some() {
+                        return Err(entry.invalid());
+                    }
+
+                    if !configured.contains(&action) {
+                        configured.push(action);
+                        map.bindings.retain(|b| b.action != action);
+                    }
+                    map.bindings.push(Binding { action, key, trigger });
+                }
+                _ => return Err(entry.invalid()),
+            }
+        }
+        Ok(map)
+    }
+
+    /// Una línea por acción con sus teclas, en el orden de `Action::ALL`.
+    pub fn describe(&self) -> Vec<String> {
+        let mut lines: Vec<String> = Action::ALL
+            .iter()
+            .filter_map(|&action| {
+                let keys: Vec<String> = self
+                    .bindings
+                    .iter()
+                    .filter(|b| b.action == action)
+                    .map(|b| key_name(b.key).to_ascii_uppercase())
+                    .collect();
+                (!keys.is_empty()).then(|| format!("{:<10} {}", keys.join(" / "), action.label()))
+            })
+            .collect();
+        if self.wheel_zoom != 0.0 {
+            lines.push(format!("{:<10} ZOOM", "RUEDA"));
+        }
+        lines.push(format!("{:<10} INSPECCIONAR OBJETO", "CLIC"));
+        lines
+    }
+}
+
+const MAX_SCROLL: f32 = 3.0;
+
+/// Estado de las acciones en el cuadro actual.
+#[derive(Debug, Clone)]
+pub struct Input {
+    pub map: InputMap,
+    active: [bool; Action::ALL.len()],
+    scroll: f32,
+    mouse_down: bool,
+    click: Option<(f32, f32)>,
+}
+
+impl Input {
+    pub fn new(map: InputMap) -> Self {
+        Input { map, active: [false; Action::ALL.len()], scroll: 0.0, mouse_down: false, click: None }
+    }
+
+    /// Lee el teclado y la rueda; llamar una vez al inicio de cada cuadro.
+    pub fn update(&mut self, window: &Window) {
+        let down = window.get_keys();
+        let pressed = window.get_keys_pressed(KeyRepeat::No);
+        let repeated = window.get_keys_pressed(KeyRepeat::Yes);
+
+        self.active = [false; Action::ALL.len()];
+        for binding in &self.map.bindings {
+            let keys = match binding.trigger {
+                Trigger::Hold => &down,
+                Trigger::Press => &pressed,
+                Trigger::Repeat => &repeated,
+            };
+            if keys.contains(&binding.key) {
+                self.active[binding.action as usize] = true;
+            }
+        }
+        // minifb da 1 por paso en X11 pero 12 en Windows; se acota para que
+        // un paso no salte demasiado
+        self.scroll = window.get_scroll_wheel().map_or(0.0, |(_, y)| y.clamp(-MAX_SCROLL, MAX_SCROLL));
+
+        let mouse_down = window.get_mouse_down(MouseButton::Left);
+        self.click = if mouse_down && !self.mouse_down {
+            window.get_mouse_pos(MouseMode::Discard)
+        } else {
+            None
+        };
+        self.mouse_down = mouse_down;
+    }
+
+    /// Posición en la ventana del clic izquierdo, solo el cuadro en que se pulsa.
+    pub fn click(&self) -> Option<(f32, f32)> {
+        self.click
+    }
+
+    pub fn is_active(&self, action: Action) -> bool {
+        self.active[action as usize]
+    }
+
+    /// Zoom pedido con la rueda en este cuadro; positivo acerca.
+    pub fn wheel_zoom(&self) -> f32 {
+        self.scroll * self.map.wheel_zoom
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_map(text: &str) -> Result<InputMap, ConfigError> {
+        InputMap::from_entries(config::parse(text)?)
+    }
+
+    fn bindings_for(map: &InputMap, action: Action) -> Vec<(Key, Trigger)> {
+        map.bindings.iter().filter(|b| b.action == action).map(|b| (b.key, b.trigger)).collect()
+    }
+
+    #[test]
+    fn key_names_round_trip() {
+        assert_eq!(parse_key("Left"), Some(Key::Left));
+        assert_eq!(parse_key("["), Some(Key::LeftBracket));
+        assert_eq!(parse_key("nope"), None);
+        for (name, key) in KEY_NAMES {
+            assert_eq!(parse_key(name), Some(*key));
+        }
+        assert_eq!(key_name(Key::Key1), "1");
+    }
+
+    #[test]
+    fn action_names_round_trip() {
+        for action in Action::ALL {
+            assert_eq!(Action::from_name(action.name()), Some(action));
+        }
+        assert_eq!(Action::from_name("fly"), None);
+    }
+
+    #[test]
+    fn binding_replaces_defaults_of_that_action_only() {
+        let map = parse_map("[bindings]\nzoom_in = z\nzoom_in = q press\n").unwrap();
+        assert_eq!(bindings_for(&map, Action::ZoomIn), vec![(Key::Z, Trigger::Hold), (Key::Q, Trigger::Press)]);
+        assert_eq!(bindings_for(&map, Action::ZoomOut), vec![(Key::Y, Trigger::Hold)]);
+    }
+
+    #[test]
+    fn explicit_triggers() {
+        let map = parse_map("[bindings]\nmidnight = n hold\nnoon = d repeat\ntoggle_sky = m press\n").unwrap();
+        assert_eq!(bindings_for(&map, Action::Midnight), vec![(Key::N, Trigger::Hold)]);
+        assert_eq!(bindings_for(&map, Action::Noon), vec![(Key::D, Trigger::Repeat)]);
+        assert_eq!(bindings_for(&map, Action::ToggleSky), vec![(Key::M, Trigger::Press)]);
+    }
+
+    #[test]
+    fn wheel_zoom_setting() {
+        let map = parse_map("[mouse]\nwheel_zoom = 0\n").unwrap();
+        assert_eq!(map.wheel_zoom, 0.0);
+    }
+
+    #[test]
+    fn invalid_bindings_are_rejected() {
+        for text in [
+            "[bindings]\nfly = x\n",
+            "[bindings]\nzoom_in = nope\n",
+            "[bindings]\nzoom_in = z sometimes\n",
+            "[bindings]\nzoom_in = z press extra\n",
+            "[bindings]\nzoom_in =\n",
+            "[mouse]\nwheel_zoom = fast\n",
+            "[other]\nzoom_in = z\n",
+        ] {
+            assert!(matches!(parse_map(text), Err(ConfigError::InvalidValue { line: 2, .. })), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn syntax_errors_report_the_line() {
+        assert!(matches!(parse_map("[bindings]\nzoom_in z\n"), Err(ConfigError::Syntax { line: 2, .. })));
+        assert!(matches!(parse_map("[bindings\n"), Err(ConfigError::Syntax { line: 1, .. })));
+    }
+}
@@ -6,12 +6,42 @@ mod light;
 mod material;
 mod cube;
 mod texture; 
+mod noise;
+mod procedural;
+mod skybox;
+mod sky;
+mod ibl;
+mod config;
+mod daycycle;
+mod fog;
+mod scene;
+mod rng;
+mod sphere;
+mod particles;
+mod transform;
+mod scene_graph;
+mod geometry;
+mod math;
+mod animation;
+mod csg;
+mod voxel;
+mod heightfield;
+mod resolution;
+mod timing;
+mod font;
+mod hud;
+mod capture;
+mod input;
+mod picking;
+mod debug_view;
+mod cli;
+mod aov;
+mod denoise;
+mod temporal;
 
 use minifb::{Window, WindowOptions, Key};
-use image::{open, DynamicImage, GenericImageView};
-use nalgebra_glm::{Vec3, normalize};
-use std::sync::Arc;
-use std::time::Duration;
+use nalgebra_glm::Vec3;
+use std::time::{Duration, Instant};
 use std::f32::consts::PI;
 use std::rc::Rc;
 
@@ -21,71 +51,36 @@ use crate::framebuffer::Framebuffer;
 use crate::camera::Camera;
 use crate::light::Light;
 use crate::cube::Cube;
-use crate::material::Material;
-use crate::texture::Texture; 
+use crate::sphere::Sphere;
+use crate::material::{Mapping, Material, MaterialOverride, MaterialParam};
+use crate::procedural::ProceduralTexture;
+use crate::skybox::Skybox;
+use crate::sky::{Sky, SkyKeyframe};
+use crate::daycycle::DayCycle;
+use crate::scene::Scene;
+use crate::particles::ParticleSystem;
+use crate::texture::Texture;
+use crate::transform::Transform;
+use crate::scene_graph::SceneNode;
+use crate::geometry::GeometryIssue;
+use crate::animation::{Animator, Clock, Easing, Keyframe, LightProperty, NodeProperty, Track};
+use crate::csg::Csg;
+use crate::voxel::{Block, VoxelGrid};
+use crate::heightfield::{Heightfield, Splat};
+use crate::resolution::DynamicResolution;
+use crate::timing::{FixedTimestep, FramePacer, FrameStats}; 
+use crate::hud::{Hud, HudInfo};
+use crate::capture::{RecordFormat, Recorder};
+use crate::input::{Action, Input, InputMap};
+use crate::picking::{Pick, SelectionCache};
+use crate::debug_view::DebugMode;
+use crate::aov::{AovBuffers, Pass};
+use crate::denoise::{Denoiser, Guides};
+use crate::temporal::TemporalAccumulator;
 
 
 const ORIGIN_BIAS: f32 = 1e-4;
 
-pub struct Skybox {
-    front: Arc<DynamicImage>,
-    back: Arc<DynamicImage>,
-    left: Arc<DynamicImage>,
-    right: Arc<DynamicImage>,
-    top: Arc<DynamicImage>,
-    bottom: Arc<DynamicImage>,
-}
-
-impl Skybox {
-    fn new(
-        front_path: &str,
-        back_path: &str,
-        left_path: &str,
-        right_path: &str,
-        top_path: &str,
-        bottom_path: &str,
-    ) -> Self {
-        Skybox {
-            front: Arc::new(image::open(front_path).expect("Failed to load front skybox image")),
-            back: Arc::new(image::open(back_path).expect("Failed to load back skybox image")),
-            left: Arc::new(image::open(left_path).expect("Failed to load left skybox image")),
-            right: Arc::new(image::open(right_path).expect("Failed to load right skybox image")),
-            top: Arc::new(image::open(top_path).expect("Failed to load top skybox image")),
-            bottom: Arc::new(image::open(bottom_path).expect("Failed to load bottom skybox image")),
-        }
-    }
-
-    fn get_color(&self, direction: &Vec3) -> Color {
-        let abs_x = direction.x.abs();
-        let abs_y = direction.y.abs();
-        let abs_z = direction.z.abs();
-
-        let (u, v, image) = if abs_x >= abs_y && abs_x >= abs_z {
-            if direction.x > 0.0 {
-                ((direction.z / abs_x + 1.0) / 2.0, (direction.y / abs_x + 1.0) / 2.0, &self.right)
-            } else {
-                ((-direction.z / abs_x + 1.0) / 2.0, (direction.y / abs_x + 1.0) / 2.0, &self.left)
-            }
-        } else if abs_y >= abs_x && abs_y >= abs_z {
-            if direction.y > 0.0 {
-                ((direction.x / abs_y + 1.0) / 2.0, (-direction.z / abs_y + 1.0) / 2.0, &self.top)
-            } else {
-                ((direction.x / abs_y + 1.0) / 2.0, (direction.z / abs_y + 1.0) / 2.0, &self.bottom)
-            }
-        } else {
-            if direction.z > 0.0 {
-                ((-direction.x / abs_z + 1.0) / 2.0, (direction.y / abs_z + 1.0) / 2.0, &self.front)
-            } else {
-                ((direction.x / abs_z + 1.0) / 2.0, (direction.y / abs_z + 1.0) / 2.0, &self.back)
-            }
-        };
-
-        let (width, height) = image.dimensions();
-        let pixel = image.get_pixel((u * width as f32) as u32, ((1.0 - v) * height as f32) as u32);
-        Color::new(pixel[0], pixel[1], pixel[2])
-    }
-}
-
 fn offset_origin(intersect: &Intersect, direction: &Vec3) -> Vec3 {
     let offset = intersect.normal * ORIGIN_BIAS;
     if direction.dot(&intersect.normal) < 0.0 {
@@ -175,165 +170,306 @@ fn cast_shadow(
 fn cast_ray(
     ray_origin: &Vec3,
     ray_direction: &Vec3,
-    objects: &[Box<dyn RayIntersect>],
-    light: &Light,
+    scene: &Scene,
     depth: u32,
-    skybox: &Skybox,
 ) -> Color {
+    trace(ray_origin, ray_direction, scene, depth).0
+}
+
+// Como `cast_ray`, pero devuelve también el impacto para las guías del filtro.
+fn trace(ray_origin: &Vec3, ray_direction: &Vec3, scene: &Scene, depth: u32) -> (Color, Intersect) {
     if depth > 3 {
-        return skybox.get_color(ray_direction);
+        return (scene.sky.get_color(ray_direction), Intersect::empty());
     }
 
-    let mut intersect = Intersect::empty();
-    let mut zbuffer = f32::INFINITY;
+    let intersect = scene.intersect(ray_origin, ray_direction);
+    let color = if intersect.is_intersecting {
+        shade(ray_origin, ray_direction, &intersect, scene, depth).total()
+    } else {
+        scene.sky.sample(ray_direction)
+    };
+    let color = Color::from_vec3(&apply_fog(&color, ray_origin, ray_direction, &intersect, scene, depth));
+    (color, intersect)
+}
 
-    for object in objects {
-        let i = object.ray_intersect(ray_origin, ray_direction);
-        if i.is_intersecting && i.distance < zbuffer {
-            zbuffer = i.distance;
-            intersect = i;
-        }
+fn apply_fog(color: &Vec3, ray_origin: &Vec3, ray_direction: &Vec3, intersect: &Intersect, scene: &Scene, depth: u32) -> Vec3 {
+    if !scene.fog.enabled {
+        return *color;
     }
-    if !intersect.is_intersecting {
-        return skybox.get_color(ray_direction);
+
+    // Niebla entre el origen del rayo y el impacto (o hasta max_distance si va al cielo)
+    let distance = if intersect.is_intersecting { intersect.distance } else { scene.fog.max_distance };
+    let ambient = scene.environment.irradiance(&Vec3::new(0.0, 1.0, 0.0));
+    let mut fogged = scene.fog.apply(color, ray_origin, ray_direction, distance, &ambient);
+    if depth == 0 {
+        fogged += scene.fog.in_scattering(ray_origin, ray_direction, distance, &scene.lights, |point, light| {
+            if scene.is_occluded(point, light) { 0.0 } else { 1.0 }
+        });
     }
+    fogged
+}
 
-    let light_dir = (light.position - intersect.point).normalize();
+/// Contribuciones por separado del sombreado de un punto, ya ponderadas;
+/// su suma es el color antes de la niebla.
+struct Shading {
+    direct: Vec3,
+    indirect: Vec3,
+    reflection: Vec3,
+    refraction: Vec3,
+    // Fracción de la luz directa que no tapa nada, de 0 a 1.
+    visibility: f32,
+}
+
+impl Shading {
+    fn total(&self) -> Vec3 {
+        self.direct + self.indirect + self.reflection + self.refraction
+    }
+}
+
+fn shade(
+    ray_origin: &Vec3,
+    ray_direction: &Vec3,
+    intersect: &Intersect,
+    scene: &Scene,
+    depth: u32,
+) -> Shading {
     let view_dir = (ray_origin - intersect.point).normalize();
-    let reflect_dir = reflect(&-light_dir, &intersect.normal).normalize();
+    let mut direct_diffuse = Vec3::zeros();
+    let mut direct_specular = Vec3::zeros();
+    let mut visible_lights = 0.0;
+    let mut active_lights = 0;
+
+    for light in &scene.lights {
+        if light.intensity <= 0.0 {
+            continue;
+        }
+        let light_dir = (light.position - intersect.point).normalize();
+        let reflect_dir = reflect(&-light_dir, &intersect.normal).normalize();
+        let light_color = Color::new(light.color[0], light.color[1], light.color[2]).to_vec3();
+
+        scene.count_ray();
+        let shadow_intensity = cast_shadow(intersect, light, &scene.objects);
+        let light_intensity = light.intensity * (1.0 - shadow_intensity);
+        let diffuse_intensity = intersect.normal.dot(&light_dir).max(0.0) * light_intensity;
+        let specular_intensity = view_dir.dot(&reflect_dir).max(0.0).powf(intersect.material.specular) * light_intensity;
+
+        direct_diffuse += light_color * diffuse_intensity;
+        direct_specular += light_color * specular_intensity;
+        visible_lights += 1.0 - shadow_intensity;
+        active_lights += 1;
+    }
 
-    let shadow_intensity = cast_shadow(&intersect, light, objects);
-    let light_intensity = light.intensity * (1.0 - shadow_intensity);
-    let specular_intensity = view_dir.dot(&reflect_dir).max(0.0).powf(intersect.material.specular);
-    let specular = Color::new(light.color[0], light.color[1], light.color[2]) * intersect.material.albedo[1] * specular_intensity * light_intensity;
+    // En materiales transparentes, la parte que refleja la superficie según
+    // el ángulo (Fresnel) se quita de la refracción y se suma al reflejo
+    let transparency = intersect.material.albedo[3];
+    let fresnel_ratio = if transparency > 0.0 {
+        fresnel(ray_direction, &intersect.normal, 1.0, intersect.material.refractive_index)
+    } else {
+        0.0
+    };
 
     let mut reflect_color = Color::black();
-    let reflectivity = intersect.material.albedo[2];
+    let reflectivity = intersect.material.albedo[2] + transparency * fresnel_ratio;
     if reflectivity > 0.0 {
         let reflect_dir = reflect(&ray_direction, &intersect.normal).normalize();
-        let reflect_origin = offset_origin(&intersect, &reflect_dir);
-        reflect_color = cast_ray(&reflect_origin, &reflect_dir, objects, light, depth + 1, skybox);
+        let reflect_origin = offset_origin(intersect, &reflect_dir);
+        reflect_color = cast_ray(&reflect_origin, &reflect_dir, scene, depth + 1);
     }
 
     let mut refract_color = Color::black();
-    let transparency = intersect.material.albedo[3];
+    let transparency = transparency * (1.0 - fresnel_ratio);
     if transparency > 0.0 {
-        let refract_dir = refract(&ray_direction, &intersect.normal, intersect.material.refractive_index);
+        let refract_dir = refract(ray_direction, &intersect.normal, intersect.material.refractive_index);
+        let refract_origin = offset_origin(intersect, &refract_dir);
+        refract_color = cast_ray(&refract_origin, &refract_dir, scene, depth + 1);
+    }
+
+    // Luz ambiente del cielo: irradiancia difusa y reflejo prefiltrado
+    let ambient = scene.environment.irradiance(&intersect.normal);
+    let view_reflect = reflect(ray_direction, &intersect.normal).normalize();
+    let environment_specular = scene.environment.specular(&view_reflect, intersect.material.specular);
+
+    let texture_color = intersect.surface_color();
+    let base_color = Color::new(texture_color[0], texture_color[1], texture_color[2]).to_vec3();
+    let [diffuse_weight, specular_weight, ..] = intersect.material.albedo;
+    let surface_weight = (1.0 - reflectivity - transparency).max(0.0);
+
+    Shading {
+        direct: (base_color.component_mul(&direct_diffuse) * diffuse_weight + direct_specular * specular_weight) * surface_weight,
+        indirect: (base_color.component_mul(&ambient) * diffuse_weight + environment_specular * specular_weight) * surface_weight,
+        reflection: reflect_color.to_vec3() * reflectivity,
+        refraction: refract_color.to_vec3() * transparency,
+        visibility: if active_lights > 0 { visible_lights / active_lights as f32 } else { 0.0 },
+    }
+}
+
+// Rebotes de reflexión o refracción que sigue `cast_ray` antes de cortar.
+fn reflection_depth(ray_origin: &Vec3, ray_direction: &Vec3, scene: &Scene, depth: u32) -> u32 {
+    if depth > 3 {
+        return depth;
+    }
+    let intersect = scene.intersect(ray_origin, ray_direction);
+    if !intersect.is_intersecting {
+        return depth;
+    }
+
+    let mut deepest = depth;
+    if intersect.material.albedo[2] > 0.0 {
+        let reflect_dir = reflect(ray_direction, &intersect.normal).normalize();
+        let reflect_origin = offset_origin(&intersect, &reflect_dir);
+        deepest = deepest.max(reflection_depth(&reflect_origin, &reflect_dir, scene, depth + 1));
+    }
+    if intersect.material.albedo[3] > 0.0 {
+        let refract_dir = refract(ray_direction, &intersect.normal, intersect.material.refractive_index);
         let refract_origin = offset_origin(&intersect, &refract_dir);
-        refract_color = cast_ray(&refract_origin, &refract_dir, objects, light, depth + 1, skybox);
+        deepest = deepest.max(reflection_depth(&refract_origin, &refract_dir, scene, depth + 1));
     }
+    deepest
+}
 
-    specular * (1.0 - reflectivity - transparency) + (reflect_color * reflectivity) + (refract_color * transparency);
-    let u = intersect.u.unwrap_or(0.0);
-    let v = intersect.v.unwrap_or(0.0);
-    let texture_color = intersect.material.get_texture_color(u, v);
+// Color de un rayo primario en los modos de depuración.
+fn debug_ray(ray_origin: &Vec3, ray_direction: &Vec3, scene: &Scene, camera: &Camera, mode: DebugMode) -> u32 {
+    if mode == DebugMode::Heatmap {
+        let tested = scene
+            .objects
+            .iter()
+            .filter(|o| o.bounds().is_none_or(|b| b.ray_range(ray_origin, ray_direction).is_some()))
+            .count();
+        return debug_view::heat_color(tested as f32 / debug_view::HEATMAP_RANGE);
+    }
+    let Some((index, intersect)) = scene.closest(ray_origin, ray_direction) else {
+        return debug_view::SKY_COLOR;
+    };
+    match mode {
+        DebugMode::Normals => debug_view::normal_color(&intersect.normal),
+        DebugMode::UvChecker => match (intersect.u, intersect.v) {
+            (Some(u), Some(v)) => debug_view::uv_checker_color(u, v),
+            _ => debug_view::MISSING_UV_COLOR,
+        },
+        DebugMode::Depth => {
+            let depth = (intersect.point - camera.eye).dot(&camera.forward());
+            debug_view::depth_color(depth)
+        }
+        DebugMode::Albedo => {
+            let [r, g, b] = intersect.surface_color();
+            Color::new(r, g, b).to_hex()
+        }
+        DebugMode::ShadowMask => {
+            let lights: Vec<&Light> = scene.lights.iter().filter(|l| l.intensity > 0.0).collect();
+            if lights.is_empty() {
+                return debug_view::gray(0.0);
+            }
+            let point = offset_origin(&intersect, &intersect.normal);
+            let lit = lights.iter().filter(|light| !scene.is_occluded(&point, light)).count();
+            debug_view::gray(lit as f32 / lights.len() as f32)
+        }
+        DebugMode::ObjectId => debug_view::id_color(index),
+        DebugMode::ReflectionDepth => {
+            let bounces = reflection_depth(ray_origin, ray_direction, scene, 0);
+            debug_view::heat_color(bounces as f32 / 4.0)
+        }
+        DebugMode::Shaded | DebugMode::Heatmap => unreachable!(),
+    }
+}
 
-    let diffuse_intensity = intersect.normal.dot(&(-ray_direction)).max(0.0).min(1.0);
-    let light_color = Color::new(texture_color[0], texture_color[1], texture_color[2]) * diffuse_intensity;
-    light_color
+/// Todas las pasadas de `aov::Pass` para un cuadro de `width` x `height`.
+pub fn render_aovs(scene: &Scene, camera: &Camera, width: usize, height: usize) -> AovBuffers {
+    let mut aovs = AovBuffers::new(width, height);
+    let forward = camera.forward();
+
+    for y in 0..height {
+        for x in 0..width {
+            let ray_direction = camera.primary_ray(x as f32, y as f32, width as f32, height as f32);
+            let Some((index, intersect)) = scene.closest(&camera.eye, &ray_direction) else {
+                let sky = scene.sky.sample(&ray_direction);
+                let beauty = apply_fog(&sky, &camera.eye, &ray_direction, &Intersect::empty(), scene, 0);
+                aovs.set(Pass::Beauty, x, y, beauty);
+                continue;
+            };
+
+            let shading = shade(&camera.eye, &ray_direction, &intersect, scene, 0);
+            let beauty = apply_fog(&shading.total(), &camera.eye, &ray_direction, &intersect, scene, 0);
+            let depth = (intersect.point - camera.eye).dot(&forward);
+            let [r, g, b] = intersect.surface_color();
+
+            aovs.set(Pass::Beauty, x, y, beauty);
+            aovs.set(Pass::Depth, x, y, Vec3::repeat(depth));
+            aovs.set(Pass::Normal, x, y, intersect.normal);
+            aovs.set(Pass::Albedo, x, y, Color::new(r, g, b).to_vec3());
+            aovs.set(Pass::Direct, x, y, shading.direct);
+            aovs.set(Pass::Indirect, x, y, shading.indirect);
+            aovs.set(Pass::Shadow, x, y, Vec3::repeat(shading.visibility));
+            aovs.set(Pass::Reflection, x, y, shading.reflection);
+            aovs.set(Pass::Refraction, x, y, shading.refraction);
+            aovs.set(Pass::ObjectId, x, y, Vec3::repeat((index + 1) as f32));
+        }
+    }
+    aovs
 }
 
-pub fn render(framebuffer: &mut Framebuffer, objects: &[Box<dyn RayIntersect>], camera: &Camera, light: &Light, skybox: &Skybox) {
+/// Renderiza la escena; con `guides` guarda además el impacto primario de
+/// cada píxel para `Denoiser::apply` (solo en `DebugMode::Shaded`).
+pub fn render(framebuffer: &mut Framebuffer, scene: &Scene, camera: &Camera, mode: DebugMode, mut guides: Option<&mut Guides>) {
     let width = framebuffer.width as f32;
     let height = framebuffer.height as f32;
-    let aspect_ratio = width / height;
-    let fov = PI / 3.0;
-    let perspective_scale = (fov * 0.5).tan();
-    let inv_width = 1.0 / width;
-    let inv_height = 1.0 / height;
+    if let Some(guides) = guides.as_deref_mut() {
+        guides.resize(framebuffer.width, framebuffer.height);
+    }
 
     for y in 0..framebuffer.height {
-        let screen_y = -(2.0 * y as f32 * inv_height) + 1.0;
-        let scaled_y = screen_y * perspective_scale;
-
         for x in 0..framebuffer.width {
-            let screen_x = (2.0 * x as f32 * inv_width) - 1.0;
-            let scaled_x = screen_x * aspect_ratio * perspective_scale;
-
-            let ray_direction = Vec3::new(scaled_x, scaled_y, -1.0).normalize();
-            let rotated_direction = camera.base_change(&ray_direction);
-
-            let pixel_color = cast_ray(&camera.eye, &rotated_direction, objects, light, 0, skybox);
+            let (jitter_x, jitter_y) = camera.jitter;
+            let ray_direction = camera.primary_ray(x as f32 + jitter_x, y as f32 + jitter_y, width, height);
+            let pixel_color = match mode {
+                DebugMode::Shaded => {
+                    let (color, intersect) = trace(&camera.eye, &ray_direction, scene, 0);
+                    if let Some(guides) = guides.as_deref_mut() {
+                        guides.set(x, y, &intersect);
+                    }
+                    color.to_hex()
+                }
+                _ => debug_ray(&camera.eye, &ray_direction, scene, camera, mode),
+            };
 
             let index = y * framebuffer.width + x;
-            framebuffer.buffer[index] = pixel_color.to_hex();
+            framebuffer.buffer[index] = pixel_color;
         }
     }
 }
-fn update_light(light: &mut Light, time_of_day: f32) {
-    let angle = time_of_day * 2.0 * PI;
-    let radius = 15.0;
-    light.position.x = radius * angle.cos();
-    light.position.y = radius * angle.sin().abs() + 5.0; 
-    light.position.z = radius * angle.sin();
-
-   
-    if time_of_day < 0.25 { // Amanecer
-        let t = time_of_day / 0.25;
-        light.color = [
-            (255.0 * t) as u8,
-            (200.0 * t) as u8,
-            (100.0 * t) as u8
-        ];
-        light.intensity = t * 2.0;
-    } else if time_of_day < 0.75 { // Día
-        light.color = [255, 255, 255];
-        light.intensity = 3.0;
-    } else { // Atardecer y noche
-        let t = (time_of_day - 0.75) / 0.25;
-        light.color = [
-            50,
-            (50.0 * (1.0 - t)) as u8,
-            (100.0 * (1.0 - t)) as u8
-        ];
-        light.intensity = 2.0 * (1.0 - t);
+fn main() {
+    let options = match cli::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    if options.help {
+        println!("{}", cli::USAGE);
+        return;
     }
-}
-
-
 
-fn main() {
-    let window_width = 800;
-    let window_height = 600;
-    let framebuffer_width = 800;
-    let framebuffer_height = 600;
+    let (window_width, window_height) = options.size;
+    let (framebuffer_width, framebuffer_height) = options.size;
     let low_res_width = framebuffer_width / 2;
     let low_res_height = framebuffer_height / 2;
-    let frame_delay = Duration::from_millis(16);
+    // Unos 60 cuadros por segundo como máximo
+    let frame_time_target = Duration::from_millis(16);
 
     let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
     let mut low_res_framebuffer = Framebuffer::new(low_res_width, low_res_height);
 
     
-    //día 
-    let original_skybox = Skybox::new(
-        "textures/skybox/right.png",
-        "textures/skybox/right.png",
-        "textures/skybox/right.png",
-        "textures/skybox/right.png",
-        "textures/skybox/right.png",
-        "textures/skybox/right.png",
-    );
-
-    //simular noche
-    let alternate_skybox = Skybox::new(
-        "textures/skybox/altern.png",
-        "textures/skybox/altern.png",
-        "textures/skybox/altern.png",
-        "textures/skybox/altern.png",
-        "textures/skybox/altern.png",
-        "textures/skybox/altern.png",
-    );
-
-    let mut current_skybox = &original_skybox;
-
-    let mut window = Window::new(
-        "ICEEE",
-        window_width,
-        window_height,
-        WindowOptions::default(),
-    )
-    .unwrap();
+    // Cielos de día y de noche: un directorio con las seis caras, una cruz
+    // o un mapa HDR; --skybox y --night-skybox cambian los de textures/skybox
+    let load_skybox = |path: &std::path::Path| {
+        Rc::new(Skybox::load(path).unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }))
+    };
+    let original_skybox = load_skybox(&options.skybox);
+    let alternate_skybox = load_skybox(&options.night_skybox);
 
     let snow_texture = Rc::new(Texture::new("textures/snow.png"));
     let snow_texture2 = Rc::new(Texture::new("textures/snow1.png"));
@@ -351,316 +487,744 @@ fn main() {
         Rc::new(Texture::new("textures/agua4.png")),
     ];
 
-    let mut animation_frame = 2;
 
 
-    let ground = Cube::new(
+    let ground = Cube::try_new(
         Vec3::new(-5.0, -1.5, -5.0),
         Vec3::new(8.0, 0.6, 8.0),
         snow_texture.clone(),
         snow_texture.clone(),
         snow_texture2.clone(),
+    ).expect("scene cube has volume")
+    .with_top_procedural(Rc::new(procedural::snow_sparkle()))
+    .with_side_procedural(Rc::new(procedural::snow_bank(0.0, 2.1)));
+
+    // Estanque helado junto a la cabaña
+    let pond = Cube::try_new(
+        Vec3::new(2.2, 0.6, 4.2),
+        Vec3::new(5.0, 0.68, 7.2),
+        snow_texture.clone(),
+        snow_texture.clone(),
+        snow_texture.clone(),
+    ).expect("scene cube has volume")
+    .with_procedural(Rc::new(procedural::ice_cracks()))
+    .with_side_procedural(Rc::new(procedural::frost()));
+
+    // Muñeco de nieve con la base en el origen del nodo: la bola de abajo se
+    // recorta con una caja para que se apoye plana y la de en medio se une
+    let snow_material = Material::new([0.9, 0.1, 0.0, 0.0], [255, 255, 255], 10.0, 1.0, None)
+        .with_procedural(Rc::new(procedural::packed_snow()));
+    let snowball = |y: f32, radius: f32| Sphere::new(Vec3::new(0.0, y, 0.0), radius, snow_material.clone());
+    let snowman_base = Csg::intersection(
+        snowball(0.7, 0.8),
+        Cube::try_new(Vec3::new(-1.0, 0.0, -1.0), Vec3::new(1.0, 1.6, 1.0), snow_texture.clone(), snow_texture.clone(), snow_texture.clone()).expect("scene cube has volume"),
     );
-
-    //Puerta
-    let door = Cube::new(
-        Vec3::new(2.0, 0.0, 0.0),
-        Vec3::new(0.5, 4.0, 3.0),  
-        door_texture.clone(),
-        door_texture.clone(),
-        door_texture.clone(),
-    );
-    
-    //wall1
-    let wall1 = Cube::new(
-        Vec3::new(3.0, 0.0, 0.0), 
-        Vec3::new(1.0, 4.0, 3.0), 
-        wall_texture.clone(),
-        wall_texture.clone(),
-        wall_texture.clone(),
+    let snowman = SceneNode::new("snowman")
+        .with_transform(Transform::translation(Vec3::new(-2.6, 0.6, 2.7)))
+        .with_child(SceneNode::leaf("body", Csg::union(snowman_base, snowball(1.75, 0.55))))
+        .with_child(SceneNode::leaf("head", snowball(2.55, 0.38)));
+
+    // Paredes de la cabaña: una caja hueca con los huecos de la puerta y las ventanas recortados
+    let wall_box = |min: Vec3, max: Vec3| {
+        Cube::try_new(min, max, wall_texture.clone(), wall_texture.clone(), wall_texture.clone()).expect("scene cube has volume")
+    };
+    let shell = Csg::difference(
+        wall_box(Vec3::new(-1.0, 0.0, -1.5), Vec3::new(5.0, 4.0, 3.0)),
+        wall_box(Vec3::new(-0.8, -0.1, -1.3), Vec3::new(4.8, 3.8, 2.8)),
     );
-    
-
-    let wall2 = Cube::new(
-        Vec3::new(-1.0, 0.0, 0.0), 
-        Vec3::new(5.0, 4.0, 3.0), 
+    let openings: Vec<Rc<dyn RayIntersect>> = vec![
+        // Puerta
+        Rc::new(wall_box(Vec3::new(0.5, -0.1, 2.7), Vec3::new(2.0, 3.2, 3.1))),
+        // Ventana del frente y de los lados
+        Rc::new(wall_box(Vec3::new(2.8, 1.6, 2.7), Vec3::new(4.2, 2.8, 3.1))),
+        Rc::new(wall_box(Vec3::new(-1.1, 1.6, 0.2), Vec3::new(-0.7, 2.8, 1.6))),
+        Rc::new(wall_box(Vec3::new(4.7, 1.6, 0.2), Vec3::new(5.1, 2.8, 1.6))),
+    ];
+    let walls = Csg::subtract_all(shell, openings);
+
+    // Puerta con la bisagra en el borde izquierdo del hueco
+    let door = SceneNode::new("door")
+        .with_transform(Transform::translation(Vec3::new(0.5, 0.0, 2.95)))
+        .with_child(SceneNode::leaf("panel", Cube::try_new(
+            Vec3::new(0.0, 0.0, -0.08),
+            Vec3::new(1.5, 3.2, 0.0),
+            door_texture.clone(),
+            door_texture.clone(),
+            door_texture.clone(),
+        ).expect("scene cube has volume")));
+
+    // Piso de baldosas dentro de la cabaña y escalón de mármol frente a la puerta;
+    // las baldosas siguen la rejilla del mundo, no la esquina del cubo
+    let floor = Cube::try_new(
+        Vec3::new(-0.8, 0.6, -1.3),
+        Vec3::new(4.8, 0.64, 2.8),
         wall_texture.clone(),
         wall_texture.clone(),
         wall_texture.clone(),
+    ).expect("scene cube has volume")
+    .with_procedural(Rc::new(procedural::floor_tiles()))
+    .with_mapping(Mapping::Solid);
+    let doorstep = Cube::try_new(
+        Vec3::new(0.3, 0.6, 3.0),
+        Vec3::new(2.2, 0.75, 3.6),
+        piedra_texture.clone(),
+        piedra_texture.clone(),
+        piedra_texture.clone(),
+    ).expect("scene cube has volume")
+    .with_procedural(Rc::new(procedural::white_marble()));
+
+    // Tocón con corteza a los lados y anillos arriba
+    let stump = Cube::try_new(
+        Vec3::new(6.0, 0.6, 1.0),
+        Vec3::new(6.6, 1.0, 1.6),
+        tronco_texture.clone(),
+        tronco_texture.clone(),
+        tronco_texture.clone(),
+    ).expect("scene cube has volume")
+    .with_top_procedural(Rc::new(procedural::stump_rings(0.3)))
+    .with_side_procedural(Rc::new(procedural::bark()));
+
+// Árbol con la base del tronco en el origen; el segundo es una copia del nodo,
+// escarchada: más brillo especular y un poco menos de difuso
+let tree1 = SceneNode::new("tree1")
+    .with_transform(Transform::translation(Vec3::new(-3.5, -1.5, -0.1)))
+    .with_child(SceneNode::leaf("tronco", Cube::try_new(
+        Vec3::new(-0.5, 0.0, -0.4),
+        Vec3::new(0.5, 5.0, 0.4),
+        tronco_texture.clone(),
+        tronco_texture.clone(),
+        tronco_texture.clone(),
+    ).expect("scene cube has volume")))
+    .with_child(SceneNode::new("copa")
+        .with_child(SceneNode::leaf("hoja1", Cube::try_new(
+            Vec3::new(-1.5, 5.0, -0.4),
+            Vec3::new(1.5, 6.0, 1.6),
+            hoja_texture.clone(),
+            hoja_texture.clone(),
+            hoja_texture.clone(),
+        ).expect("scene cube has volume")))
+        .with_child(SceneNode::leaf("hoja2", Cube::try_new(
+            Vec3::new(-1.0, 6.0, -0.4),
+            Vec3::new(1.0, 7.0, 1.6),
+            hoja_texture.clone(),
+            hoja_texture.clone(),
+            hoja_texture.clone(),
+        ).expect("scene cube has volume")))
+        .with_child(SceneNode::leaf("hoja3", Cube::try_new(
+            Vec3::new(-1.25, 5.0, -1.4),
+            Vec3::new(1.25, 6.0, 1.6),
+            hoja_texture.clone(),
+            hoja_texture.clone(),
+            hoja_texture.clone(),
+        ).expect("scene cube has volume")))
+        .with_child(SceneNode::leaf("hoja4", Cube::try_new(
+            Vec3::new(-1.25, 6.0, 0.6),
+            Vec3::new(1.25, 7.0, 1.6),
+            hoja_texture.clone(),
+            hoja_texture.clone(),
+            hoja_texture.clone(),
+        ).expect("scene cube has volume"))));
+
+let tree2 = tree1
+    .duplicate("tree2")
+    .with_transform(Transform::from_trs(Vec3::new(6.0, -1.5, 6.25), 0.6, 0.8))
+    .with_material(
+        MaterialOverride::default()
+            .with(MaterialParam::Diffuse, 0.7)
+            .with(MaterialParam::Specular, 0.4)
+            .with(MaterialParam::Shininess, 40.0),
     );
-    
-
-let back_left = Cube::new(
-    Vec3::new(-1.0, 0.0, -1.5),      
-    Vec3::new(5.0, 4.0, 0.1),         
-    wall_texture.clone(),
-    wall_texture.clone(),
-    wall_texture.clone(),
-);
-
-
-let back_right = Cube::new(
-    Vec3::new(3.0, 0.0, -1.5),       
-    Vec3::new(5.0, 4.0, 0.1),         
-    wall_texture.clone(),
-    wall_texture.clone(),
-    wall_texture.clone(),
-);
-
-
-// Tronco del árbol 
-let tronco = Cube::new(
-    Vec3::new(-4.0, -1.5, -0.5),  
-    Vec3::new(-3.0, 3.5, 0.3),  
-    tronco_texture.clone(),
-    tronco_texture.clone(),
-    tronco_texture.clone(),
-);
-
-// Hoja del árbol 
-let hoja1 = Cube::new(
-    Vec3::new(-5.0, 3.5, -0.5),    
-    Vec3::new(-2.0, 4.5, 1.5),    
-    hoja_texture.clone(),           
-    hoja_texture.clone(),
-    hoja_texture.clone(),
-);
-
-// Hoja del árbol
-let hoja2 = Cube::new(
-    Vec3::new(-4.5, 4.5, -0.5),    
-    Vec3::new(-2.5, 5.5, 1.5),      
-    hoja_texture.clone(),            
-    hoja_texture.clone(),
-    hoja_texture.clone(),
-);
-
-// Hoja del árbol
-let hoja3 = Cube::new(
-    Vec3::new(-4.75, 3.5, -1.5),    
-    Vec3::new(-2.25, 4.5, 1.5),      
-    hoja_texture.clone(),             
-    hoja_texture.clone(),
-    hoja_texture.clone(),
-);
-
-// Hoja del árbol 
-let hoja4 = Cube::new(
-    Vec3::new(-4.75, 4.5, 0.5),     
-    Vec3::new(-2.25, 5.5, 1.5),      
-    hoja_texture.clone(),             
-    hoja_texture.clone(),
-    hoja_texture.clone(),
-);
 
 
 // Techo de la casa
-let techo1 = Cube::new(
+// Triplanar para que los tres bloques del techo compartan la misma textura
+let roof_mapping = Mapping::Triplanar { scale: 0.25, sharpness: 4.0 };
+
+let techo1 = Cube::try_new(
     Vec3::new(-2.0, 5.0, -2.0),  
     Vec3::new(6.0, 4.0, 3.0),  
     techo_texture.clone(),
     techo_texture.clone(),
     techo_texture.clone(),
-);
+).expect("scene cube has volume").with_mapping(roof_mapping);
 
-let techo2 = Cube::new(
+let techo2 = Cube::try_new(
     Vec3::new(-1.0, 5.5, -2.0),  
     Vec3::new(5.0, 4.0, 3.0),   
     techo_texture.clone(),
     techo_texture.clone(),
     techo_texture.clone(),
-);
+).expect("scene cube has volume").with_mapping(roof_mapping);
 
-let techo3 = Cube::new(
+let techo3 = Cube::try_new(
     Vec3::new(0.0, 6.0, -2.0),  
     Vec3::new(4.0, 4.0, 3.0),   
     techo_texture.clone(),
     techo_texture.clone(),
     techo_texture.clone(),
+).expect("scene cube has volume").with_mapping(roof_mapping);
+
+// Un jacuzzi por cada cuadro de la animación del agua, con bordes de piedra
+let stone: Rc<dyn ProceduralTexture> = Rc::new(procedural::stone());
+let jacuzzi_frames: Vec<Rc<dyn RayIntersect>> = agua_textures
+    .iter()
+    .map(|agua| {
+        Rc::new(Cube::try_new(
+            Vec3::new(-4.0, -1.5, 4.5),
+            Vec3::new(1.0, 1.2, 7.0),
+            agua.clone(),
+            agua.clone(),
+            piedra_texture.clone(),
+        ).expect("scene cube has volume").with_side_procedural(stone.clone())) as Rc<dyn RayIntersect>
+    })
+    .collect();
+
+
+
+// Colina de bloques detrás de la cabaña: nieve encima, tierra y piedra debajo
+let dirt_texture = Rc::new(Texture::new("textures/dirt.png"));
+let mut hill = VoxelGrid::new(Vec3::new(-5.0, 0.6, -5.0), 0.25, [52, 24, 12]);
+let snow_block = hill.add_block(Block::new(snow_texture.clone(), dirt_texture.clone(), snow_texture2.clone()));
+let dirt_block = hill.add_block(Block::uniform(dirt_texture.clone()));
+let stone_block = hill.add_block(Block::uniform(piedra_texture.clone()));
+hill.fill_heightmap(
+    |x, z| {
+        if x >= 52 || z >= 12 {
+            return 0;
+        }
+        let back = 1.0 - z as f32 / 12.0;
+        let edges = ((x as f32 / 8.0).min((51 - x) as f32 / 8.0)).min(1.0);
+        let bumps = 0.8 + 0.4 * noise::fbm(&Vec3::new(x as f32 * 0.12, 0.0, z as f32 * 0.12), 3, 2.0, 0.5, 7);
+        (22.0 * back * edges * bumps) as usize
+    },
+    |_, depth| match depth {
+        0 => snow_block,
+        1..=2 => dirt_block,
+        _ => stone_block,
+    },
 );
 
-// Tronco del árbol 2
-let tronco2 = Cube::new(
-    Vec3::new(5.5, -1.5, 6.0),   
-    Vec3::new(6.5, 3.5, 6.5),    
-    tronco_texture.clone(),
-    tronco_texture.clone(),
-    tronco_texture.clone(),
-);
-
-// Hoja del árbol 1 
-let hoja1_2 = Cube::new(
-    Vec3::new(4.3, 3.5, 6.0),   
-    Vec3::new(6.8, 4.5, 7.0),   
-    hoja_texture.clone(),           
-    hoja_texture.clone(),
-    hoja_texture.clone(),
-);
-
-// Hoja del árbol 
-let hoja2_2 = Cube::new(
-    Vec3::new(4.8, 4.5, 6.0),    
-    Vec3::new(6.6, 5.0, 7.0),     
-    hoja_texture.clone(),           
-    hoja_texture.clone(),
-    hoja_texture.clone(),
-);
-
-// Hoja del árbol 
-let hoja3_2 = Cube::new(
-    Vec3::new(4.5, 3.5, 5.8),   
-    Vec3::new(6.5, 4.5, 6.5),     
-    hoja_texture.clone(),           
-    hoja_texture.clone(),
-    hoja_texture.clone(),
-);
-
-
-let hoja4_2 = Cube::new(
-    Vec3::new(4.8, 4.0, 6.5),   
-    Vec3::new(6.6, 5.0, 7.5),     
-    hoja_texture.clone(),           
-    hoja_texture.clone(),
-    hoja_texture.clone(),
-);
-
-
-let mut jacuzzi = Cube::new(
-    Vec3::new(-4.0, -1.5, 4.5),  
-    Vec3::new(1.0, 1.0, 6.5),    
-    agua_textures[0].clone(),      
-    agua_textures[0].clone(),
-    piedra_texture.clone(),
+// Valle alrededor de la isla: casi plano cerca de ella y con montañas al fondo.
+// Con --heightmap se lee de una imagen que cubre la misma zona
+let terrain_origin = Vec3::new(-58.5, -1.0, -58.5);
+let terrain = match &options.heightmap {
+    Some(path) => Heightfield::from_image(path, terrain_origin, (120.0, 120.0), 16.0).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }),
+    None => Heightfield::from_fn([193, 193], terrain_origin, (120.0, 120.0), |x, z| {
+        let distance = ((x - 1.5).powi(2) + (z - 1.5).powi(2)).sqrt();
+        let ramp = math::smoothstep(9.0, 45.0, distance);
+        let mountains = 0.55 + 0.45 * noise::fbm(&Vec3::new(x * 0.04, 0.0, z * 0.04), 5, 2.0, 0.5, 3);
+        let bumps = 0.4 * noise::fbm(&Vec3::new(x * 0.2, 0.0, z * 0.2), 3, 2.0, 0.5, 5);
+        ramp * 16.0 * mountains + bumps
+    })
+    .expect("terrain has at least 2x2 samples"),
+}
+.with_splat(
+    Splat::new(snow_texture.clone(), dirt_texture.clone(), Rc::new(Texture::new("textures/stone.png")))
+        .with_snow_line(-0.8, 0.6)
+        .with_steep_slope(0.7),
 );
 
-
-
     let mut camera = Camera::new(
         Vec3::new(0.0, 5.0, 15.0),
         Vec3::new(0.0, 0.0, 0.0),
         Vec3::new(0.0, 1.0, 0.0),
     );
 
-    let mut light = Light::new(
-        Vec3::new(10.0, 15.0, 10.0),
-        [255, 255, 255],
-        1.0,
-    );
+    // Sol y luna; el ciclo de día los coloca cada cuadro. El farol de la
+    // puerta lo mueve el animador
+    const LANTERN: usize = 2;
+    let lantern_position = Vec3::new(1.25, 3.0, 3.6);
+    let lights = vec![
+        Light::new(Vec3::new(10.0, 15.0, 10.0), [255, 255, 255], 1.0),
+        Light::new(Vec3::new(-10.0, -15.0, -10.0), [150, 170, 220], 0.0),
+        Light::new(lantern_position, [255, 170, 80], 0.15),
+    ];
+
+    let mut day_cycle = DayCycle::from_file("config/daycycle.cfg").unwrap_or_else(|err| {
+        eprintln!("Using default day cycle: {}", err);
+        DayCycle::default()
+    });
 
-    let mut time_of_day: f32 = 0.0; 
-    let day_cycle_speed: f32 = 0.001; 
+    let input_map = InputMap::from_file("config/controls.cfg").unwrap_or_else(|err| {
+        eprintln!("Using default controls: {}", err);
+        InputMap::default()
+    });
+    let mut input = Input::new(input_map);
 
     let rotation_speed = PI / 10.0;
     let zoom_speed = 1.0;
 
-    let mut objects: Vec<Box<dyn RayIntersect>> = vec![
-        Box::new(ground),
-        Box::new(door),
-        Box::new(tronco),
-        Box::new(hoja1),
-        Box::new(hoja2),
-        Box::new(hoja3),
-        Box::new(hoja4),
-        Box::new(wall1),
-        Box::new(wall2),
-        Box::new(back_left),
-        Box::new(back_right),
-        Box::new(techo1),
-        Box::new(techo2),
-        Box::new(techo3),
-        Box::new(tronco2),
-        Box::new(hoja1_2),
-        Box::new(hoja2_2),
-        Box::new(hoja3_2),
-        Box::new(hoja4_2),
-        Box::new(jacuzzi),
-
-    ];
+    let cabin = SceneNode::new("cabin")
+        .with_child(SceneNode::new("walls").with_object(walls))
+        .with_child(SceneNode::leaf("floor", floor))
+        .with_child(SceneNode::leaf("doorstep", doorstep))
+        .with_child(door)
+        .with_child(SceneNode::new("techo")
+            .with_child(SceneNode::leaf("techo1", techo1))
+            .with_child(SceneNode::leaf("techo2", techo2))
+            .with_child(SceneNode::leaf("techo3", techo3)));
+
+    let root = SceneNode::new("root")
+        .with_child(SceneNode::leaf("ground", ground))
+        .with_child(SceneNode::leaf("pond", pond))
+        .with_child(snowman)
+        .with_child(SceneNode::leaf("stump", stump))
+        .with_child(SceneNode::leaf("hill", hill))
+        .with_child(SceneNode::leaf("terrain", terrain))
+        .with_child(cabin)
+        .with_child(SceneNode::new("trees").with_child(tree1).with_child(tree2))
+        .with_child(SceneNode::new("jacuzzi").with_object(jacuzzi_frames[0].clone()));
+
+    // Cuatro cuadros de agua por segundo, en bucle
+    let animator = Animator::new()
+        .with_node(
+            "jacuzzi",
+            NodeProperty::Frames {
+                frames: jacuzzi_frames,
+                frame: Track::new((0..=4).map(|i| Keyframe::new(i as f32 * 0.25, i as f32).with_easing(Easing::Step)).collect())
+                    .looping(),
+            },
+        )
+        // La puerta se abre hacia dentro y se vuelve a cerrar cada 12 segundos
+        .with_node(
+            "door",
+            NodeProperty::Rotation {
+                axis: Vec3::new(0.0, 1.0, 0.0),
+                angle: Track::new(vec![
+                    Keyframe::new(0.0, 0.0),
+                    Keyframe::new(4.0, 0.0).with_easing(Easing::EaseInOut),
+                    Keyframe::new(6.0, 1.3),
+                    Keyframe::new(10.0, 1.3).with_easing(Easing::EaseInOut),
+                    Keyframe::new(12.0, 0.0),
+                ])
+                .looping(),
+            },
+        )
+        // El muñeco de nieve da un salto cada 5 segundos: sube frenando, cae
+        // acelerando y se aplasta un poco al aterrizar
+        .with_node(
+            "snowman",
+            NodeProperty::Translation(
+                Track::new(vec![
+                    Keyframe::new(0.0, Vec3::zeros()),
+                    Keyframe::new(4.0, Vec3::zeros()).with_easing(Easing::EaseOut),
+                    Keyframe::new(4.3, Vec3::new(0.0, 0.35, 0.0)).with_easing(Easing::EaseIn),
+                    Keyframe::new(4.6, Vec3::zeros()),
+                    Keyframe::new(5.0, Vec3::zeros()),
+                ])
+                .looping(),
+            ),
+        )
+        .with_node(
+            "snowman",
+            NodeProperty::Scale(
+                Track::new(vec![
+                    Keyframe::new(0.0, Vec3::new(1.0, 1.0, 1.0)),
+                    Keyframe::new(4.6, Vec3::new(1.0, 1.0, 1.0)),
+                    Keyframe::new(4.7, Vec3::new(1.08, 0.88, 1.08)),
+                    Keyframe::new(5.0, Vec3::new(1.0, 1.0, 1.0)),
+                ])
+                .looping(),
+            ),
+        )
+        // El hielo del estanque se vuelve más brillante un rato cada 16 segundos
+        .with_node(
+            "pond",
+            NodeProperty::Material(
+                MaterialParam::Reflectivity,
+                Track::new(vec![
+                    Keyframe::new(0.0, 0.1),
+                    Keyframe::new(8.0, 0.1).with_easing(Easing::EaseInOut),
+                    Keyframe::new(10.0, 0.35),
+                    Keyframe::new(14.0, 0.35).with_easing(Easing::EaseInOut),
+                    Keyframe::new(16.0, 0.1),
+                ])
+                .looping(),
+            ),
+        )
+        // El farol se mece colgado del alero y su llama parpadea
+        .with_light(
+            LANTERN,
+            LightProperty::Position(
+                Track::new(vec![
+                    Keyframe::new(0.0, lantern_position + Vec3::new(-0.08, 0.0, 0.0)).with_easing(Easing::EaseInOut),
+                    Keyframe::new(1.5, lantern_position + Vec3::new(0.08, 0.0, 0.0)).with_easing(Easing::EaseInOut),
+                    Keyframe::new(3.0, lantern_position + Vec3::new(-0.08, 0.0, 0.0)),
+                ])
+                .looping(),
+            ),
+        )
+        .with_light(
+            LANTERN,
+            LightProperty::Intensity(
+                Track::new(vec![
+                    Keyframe::new(0.0, 0.15),
+                    Keyframe::new(0.2, 0.11),
+                    Keyframe::new(0.35, 0.17),
+                    Keyframe::new(0.6, 0.13),
+                    Keyframe::new(0.8, 0.16),
+                    Keyframe::new(1.1, 0.15),
+                ])
+                .looping(),
+            ),
+        )
+        .with_light(
+            LANTERN,
+            LightProperty::Color(
+                Track::new(vec![
+                    Keyframe::new(0.0, Vec3::new(255.0, 170.0, 80.0)),
+                    Keyframe::new(0.45, Vec3::new(255.0, 145.0, 60.0)),
+                    Keyframe::new(0.9, Vec3::new(255.0, 170.0, 80.0)),
+                ])
+                .looping(),
+            ),
+        );
+    // Un solo reloj para animaciones y ciclo de día, a propósito: pausar,
+    // retroceder o avanzar un cuadro congela o mueve también el sol y la luna,
+    // así se puede inspeccionar un instante completo de la escena
+    let mut clock = Clock::default();
 
     const DAY_START: f32 = 0.25;
     const DAY_END: f32 = 0.75;
-    let mut time_of_day: f32 = 0.5; // Comienza al mediodía
-    let day_cycle_speed: f32 = 0.001;
-    
-
-    while window.is_open() && !window.is_key_down(Key::Escape) {
-        if window.is_key_down(Key::Left) {
+    const SKY_BLEND: f32 = 0.05;
+
+    // Transición suave entre el cielo de día y el de noche; M alterna con el cielo procedural
+    let sky = Sky::with_maps(
+        vec![
+            SkyKeyframe::new(DAY_START, original_skybox.clone()),
+            SkyKeyframe::new(DAY_END, alternate_skybox.clone()),
+        ],
+        SKY_BLEND,
+    );
+    // Los solapes entre cajas son habituales en esta escena; solo se cuentan,
+    // salvo con --verbose, que los lista con los nodos implicados
+    let issues = root.validate();
+    let overlaps = issues.iter().filter(|i| matches!(i, GeometryIssue::Overlap { .. })).count();
+    for issue in &issues {
+        if options.verbose || !matches!(issue, GeometryIssue::Overlap { .. }) {
+            eprintln!("Scene warning: {}", issue);
+        }
+    }
+    if overlaps > 0 && !options.verbose {
+        eprintln!("Scene: {} overlapping object pairs (--verbose lists them)", overlaps);
+    }
+    let mut scene = Scene::new(root, lights, sky);
+    scene.environment.intensity = 0.6;
+    let fog_height_density = scene.fog.height_density;
+
+    // Nieve cayendo sobre la escena, con semilla fija para que sea reproducible
+    let mut snow = ParticleSystem::snowfall(Vec3::new(-6.0, 0.6, -6.0), Vec3::new(9.0, 10.0, 9.0), 0.6, 42);
+    snow.prewarm(10.0);
+    scene.particles.push(snow);
+    let mut environment_time = -1.0;
+    let mut environment_mode = scene.sky.mode;
+    let mut last_frame = Instant::now();
+    let mut timestep = FixedTimestep::new(1.0 / 60.0);
+    let mut pacer = FramePacer::new(frame_time_target);
+    let mut frame_stats = FrameStats::new(30);
+    let mut title_timer = 0.0;
+    let mut resolution = DynamicResolution::default();
+    resolution.filter = options.upscale;
+    let mut last_eye = camera.eye;
+    let mut last_center = camera.center;
+    let mut hud = Hud::default().with_controls(input.map.describe());
+    let capture_dir = std::path::Path::new("captures");
+    let mut recorder: Option<Recorder> = None;
+    let mut selection: Option<Pick> = None;
+    let mut selection_cache = SelectionCache::default();
+    let mut render_mode = options.mode;
+    let mut denoiser = Denoiser { enabled: options.denoise, ..Denoiser::default() };
+    let mut guides = Guides::default();
+    let mut temporal = TemporalAccumulator::default();
+
+    if let Some(time) = options.time_of_day {
+        day_cycle.set_time(time);
+    }
+    clock.set_time(options.clock_time);
+    // Sin ventana se renderiza un único cuadro tal cual, sin HUD ni simulación
+    let mut window = if options.headless {
+        clock.set_paused(true);
+        resolution.enabled = false;
+        temporal.enabled = false;
+        hud.visible = false;
+        None
+    } else {
+        Some(Window::new("ICEEE", window_width, window_height, WindowOptions::default()).unwrap())
+    };
+
+    loop {
+        pacer.begin_frame();
+        if let Some(window) = &window {
+            if !window.is_open() || window.is_key_down(Key::Escape) {
+                break;
+            }
+            input.update(window);
+        }
+        if input.is_active(Action::OrbitLeft) {
             camera.orbit(rotation_speed, 0.0);
         }
 
-        if window.is_key_down(Key::Right) {
+        if input.is_active(Action::OrbitRight) {
             camera.orbit(-rotation_speed, 0.0);
         }
 
-        if window.is_key_down(Key::Up) {
+        if input.is_active(Action::OrbitUp) {
             camera.orbit(0.0, -rotation_speed);
         }
 
-        if window.is_key_down(Key::Down) {
+        if input.is_active(Action::OrbitDown) {
             camera.orbit(0.0, rotation_speed);
         }
          // Zoom de la cámara
-        if window.is_key_down(Key::Y) {
+        if input.is_active(Action::ZoomOut) {
             camera.zoom(-zoom_speed);  // alejar
         }
-        if window.is_key_down(Key::X) {
+        if input.is_active(Action::ZoomIn) {
             camera.zoom(zoom_speed);   // acercar
         }
-        if window.is_key_down(Key::N) {
-            time_of_day = 0.0; // Medianoche
+        let wheel_zoom = input.wheel_zoom();
+        if wheel_zoom != 0.0 {
+            camera.zoom(wheel_zoom);
+        }
+        if input.is_active(Action::Midnight) {
+            day_cycle.set_time(0.0); // Medianoche
         }
         
-        if window.is_key_down(Key::D) {
-            time_of_day = 0.5; // Mediodía
+        if input.is_active(Action::Noon) {
+            day_cycle.set_time(0.5); // Mediodía
         }
     
         // Controles para ajustar manualmente el tiempo
-        if window.is_key_down(Key::W) {
-            time_of_day = (time_of_day + 0.01).min(1.0);
+        if input.is_active(Action::TimeForward) {
+            day_cycle.set_time(day_cycle.time_of_day + 0.01);
         }
-        if window.is_key_down(Key::S) {
-            time_of_day = (time_of_day - 0.01).max(0.0);
+        if input.is_active(Action::TimeBackward) {
+            day_cycle.set_time(day_cycle.time_of_day - 0.01);
         }
     
-        // Avance automático del tiempo
-        time_of_day += day_cycle_speed;
-        if time_of_day >= 1.0 {
-            time_of_day -= 1.0;
+        // Reloj de la escena: pausa, retroceder o adelantar y avanzar un cuadro
+        if input.is_active(Action::TogglePause) {
+            clock.toggle_pause();
         }
+        if input.is_active(Action::ScrubBackward) {
+            clock.scrub(-0.5);
+        }
+        if input.is_active(Action::ScrubForward) {
+            clock.scrub(0.5);
+        }
+        if input.is_active(Action::StepFrame) {
+            clock.step(1.0 / 30.0);
+        }
+        if let Some(mode) = Action::ALL.into_iter().filter(|a| input.is_active(*a)).find_map(Action::debug_mode) {
+            render_mode = mode;
+        }
+
+        // Tiempo real del cuadro, repartido en pasos fijos de simulación
+        let now = Instant::now();
+        let real_dt = (now - last_frame).as_secs_f32();
+        last_frame = now;
+        let dt = clock.tick(real_dt);
+        if dt < 0.0 {
+            // Retroceder con el reloj solo afecta a la hora del día
+            day_cycle.advance(dt);
+        }
+        for _ in 0..timestep.advance(dt) {
+            day_cycle.advance(timestep.step);
+            for particles in &mut scene.particles {
+                particles.update(timestep.step);
+            }
+        }
+        let time_of_day = day_cycle.time_of_day;
     
-        // Actualiza la luz basada en el tiempo del día
-        update_light(&mut light, time_of_day);
-    
-        // Determina el skybox actual
-        current_skybox = if time_of_day > DAY_START && time_of_day < DAY_END {
-            &original_skybox // Día
-        } else {
-            &alternate_skybox // Noche
-        };
+        // Actualiza el sol y la luna basados en el tiempo del día
+        day_cycle.update_sun(&mut scene.lights[0]);
+        day_cycle.update_moon(&mut scene.lights[1]);
     
-        animation_frame = (animation_frame + 3) % agua_textures.len();
+        if input.is_active(Action::ToggleSky) {
+            scene.sky.toggle_mode();
+        }
+        if input.is_active(Action::ToggleFog) {
+            scene.fog.enabled = !scene.fog.enabled;
+            temporal.reset();
+        }
+        if input.is_active(Action::ToggleResolution) {
+            resolution.toggle();
+        }
+        if input.is_active(Action::ToggleHud) {
+            hud.toggle();
+        }
+        if input.is_active(Action::ToggleDenoise) {
+            denoiser.toggle();
+        }
+        if input.is_active(Action::ToggleTemporal) {
+            temporal.toggle();
+        }
 
-        jacuzzi = Cube::new(
-            Vec3::new(-4.0, -1.5, 4.5), 
-            Vec3::new(1.0, 1.2, 7.0),
-            agua_textures[animation_frame].clone(),
-            agua_textures[animation_frame].clone(),
-            piedra_texture.clone(),
-        );
-        
+        // El cielo sigue la misma hora que la luz
+        scene.sky.update(&day_cycle);
+
+        // Reproyectar la luz del cielo solo cuando cambia de forma apreciable.
+        // Con la luz cambia también lo acumulado (saltos de hora, el sol que
+        // avanza o el cielo alternado), así que la historia ya no sirve
+        if scene.sky.mode != environment_mode || (time_of_day - environment_time).abs() >= 0.005 {
+            scene.environment.update(&scene.sky);
+            temporal.reset();
+            environment_time = time_of_day;
+            environment_mode = scene.sky.mode;
+        }
 
-        
+        // Neblina más densa al amanecer
+        scene.fog.height_density = fog_height_density * (0.25 + 0.75 * fog::morning_mist(time_of_day));
+    
+        animator.apply(&mut scene, clock.time());
+
+
+        // Pasadas para composición: se renderizan aparte del cuadro normal
+        if let (None, Some(dir)) = (&window, &options.aov_dir) {
+            let aovs = render_aovs(&scene, &camera, framebuffer.width, framebuffer.height);
+            match aovs.write(dir, options.aov_format) {
+                Ok(paths) => println!("Wrote {} passes to {}", paths.len(), dir.display()),
+                Err(err) => {
+                    eprintln!("AOV export failed: {}", err);
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
 
-        let len = objects.len();
-        objects[len - 1] = Box::new(jacuzzi); 
+        // Media resolución mientras la cámara se mueve; se refina al quedarse quieta
+        let camera_moved = camera.eye != last_eye || camera.center != last_center;
+        last_eye = camera.eye;
+        last_center = camera.center;
+        let render_start = Instant::now();
+        let low_res = resolution.begin_frame(camera_moved);
+        let target = if low_res { &mut low_res_framebuffer } else { &mut framebuffer };
+        // Acumulación y filtro se aplican antes de escalar, sobre los píxeles
+        // trazados; los modos de depuración se ven sin ellos
+        let shaded = render_mode == DebugMode::Shaded;
+        if !shaded {
+            temporal.reset();
+        }
+        camera.jitter = if shaded { temporal.next_jitter() } else { (0.0, 0.0) };
+        let use_guides = shaded && (denoiser.enabled || temporal.enabled);
+        render(target, &scene, &camera, render_mode, use_guides.then_some(&mut guides));
+        if shaded {
+            temporal.accumulate(target, &guides, &camera);
+        }
+        if shaded && denoiser.enabled {
+            denoiser.apply(target, &guides);
+        }
+        if low_res {
+            low_res_framebuffer.upscale_into(&mut framebuffer, resolution.filter);
+        }
+        resolution.end_frame(render_start.elapsed());
 
+        // Capturas sin el HUD: una imagen suelta o una grabación en PNG o en crudo
+        if input.is_active(Action::Screenshot) {
+            match capture::screenshot(&framebuffer, capture_dir) {
+                Ok(path) => println!("Screenshot saved to {}", path.display()),
+                Err(err) => eprintln!("Screenshot failed: {}", err),
+            }
+        }
+        let record_png = input.is_active(Action::ToggleRecording);
+        let record_raw = input.is_active(Action::ToggleRawRecording);
+        if record_png || record_raw {
+            match recorder.take() {
+                Some(active) => finish_recording(active),
+                None => {
+                    let format = if record_raw { RecordFormat::Raw } else { RecordFormat::Png };
+                    match Recorder::start(capture_dir, format) {
+                        Ok(active) => recorder = Some(active),
+                        Err(err) => eprintln!("Recording failed: {}", err),
+                    }
+                }
+            }
+        }
+        if let Some(active) = &mut recorder {
+            if let Err(err) = active.record(&framebuffer, clock.time(), time_of_day) {
+                eprintln!("Recording stopped: {}", err);
+                recorder = None;
+            }
+        }
 
-        render(&mut framebuffer, &objects, &camera, &light, current_skybox);
+        // Clic: selecciona lo que hay bajo el cursor (o nada si es el cielo)
+        if let (Some(window), Some((mouse_x, mouse_y))) = (&window, input.click()) {
+            let (window_w, window_h) = window.get_size();
+            let x = mouse_x * framebuffer.width as f32 / window_w as f32;
+            let y = mouse_y * framebuffer.height as f32 / window_h as f32;
+            selection = picking::pick(&scene, &camera, x, y, framebuffer.width, framebuffer.height);
+            match &selection {
+                Some(pick) => println!("{}", pick.describe().join("\n  ")),
+                None => println!("Nothing under the cursor"),
+            }
+        }
+        if let Some(index) = selection.as_ref().and_then(|pick| pick.current_index(&scene)) {
+            let mask = selection_cache.mask(&scene, &camera, index, framebuffer.width, framebuffer.height);
+            framebuffer.set_current_color(0xFFD700);
+            picking::draw_outline(&mut framebuffer, mask, 2);
+        }
 
+        hud.draw(&mut framebuffer, &HudInfo {
+            fps: frame_stats.fps(),
+            frame_ms: frame_stats.average_ms(),
+            time_of_day,
+            camera_eye: camera.eye,
+            rays: scene.take_ray_count(),
+            low_res: resolution.is_low_res(),
+            paused: clock.is_paused(),
+            fog: scene.fog.enabled,
+            recording: recorder.is_some(),
+            denoise: denoiser.enabled,
+            temporal: temporal.enabled,
+            selection: selection.as_ref().map_or_else(Vec::new, |pick| pick.describe()),
+            mode: render_mode,
+        });
+
+        let Some(window) = &mut window else {
+            match capture::save_png(&framebuffer, &options.output) {
+                Ok(()) => println!("Saved {} render to {}", render_mode.name(), options.output.display()),
+                Err(err) => {
+                    eprintln!("Headless render failed: {}", err);
+                    std::process::exit(1);
+                }
+            }
+            return;
+        };
         window
             .update_with_buffer(&framebuffer.buffer, framebuffer_width, framebuffer_height)
             .unwrap();
 
-        std::thread::sleep(frame_delay);
+        // FPS y tiempo por cuadro en el título, dos veces por segundo
+        frame_stats.record(real_dt);
+        title_timer += real_dt;
+        if title_timer >= 0.5 {
+            title_timer = 0.0;
+            window.set_title(&format!(
+                "ICEEE - {:.1} FPS - {:.1} ms (max {:.1}){}{}",
+                frame_stats.fps(),
+                frame_stats.average_ms(),
+                frame_stats.max_ms(),
+                if resolution.is_low_res() { " - half res" } else { "" },
+                if clock.is_paused() { " - paused" } else { "" },
+            ));
+        }
+
+        pacer.end_frame();
+    }
+
+    // Al cerrar la ventana con una grabación en curso también hay que cerrarla
+    if let Some(active) = recorder.take() {
+        finish_recording(active);
+    }
+}
+
+fn finish_recording(recorder: Recorder) {
+    let (dir, frames) = (recorder.dir().to_path_buf(), recorder.frame_count());
+    match recorder.finish() {
+        Ok(()) => println!("Recorded {} frames to {}", frames, dir.display()),
+        Err(err) => eprintln!("Recording failed: {}", err),
     }
 }
\ No newline at end of file
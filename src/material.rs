@@ -1,5 +1,77 @@
 use std::rc::Rc;
+use nalgebra_glm::Vec3;
 use crate::texture::Texture;
+use crate::procedural::ProceduralTexture;
+
+/// Cómo se obtiene el color de la superficie a partir del punto de impacto.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mapping {
+    // UV por cara, calculadas por cada primitiva.
+    Uv,
+    // Proyección sobre los tres planos del mundo; `scale` repeticiones por
+    // unidad y `sharpness` controla la mezcla entre planos.
+    Triplanar { scale: f32, sharpness: f32 },
+    // La textura procedural se evalúa en coordenadas del mundo.
+    Solid,
+}
+
+/// Parámetro numérico de un material que puede sobrescribirse o animarse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaterialParam {
+    Diffuse,
+    Specular,
+    Reflectivity,
+    Transparency,
+    // Exponente de Phong
+    Shininess,
+    RefractiveIndex,
+}
+
+/// Valores que reemplazan a los del material de un objeto al intersectarlo.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MaterialOverride {
+    values: Vec<(MaterialParam, f32)>,
+}
+
+impl MaterialOverride {
+    pub fn with(mut self, param: MaterialParam, value: f32) -> Self {
+        self.set(param, value);
+        self
+    }
+
+    pub fn set(&mut self, param: MaterialParam, value: f32) {
+        match self.values.iter_mut().find(|(p, _)| *p == param) {
+            Some(entry) => entry.1 = value,
+            None => self.values.push((param, value)),
+        }
+    }
+
+    pub fn get(&self, param: MaterialParam) -> Option<f32> {
+        self.values.iter().find(|(p, _)| *p == param).map(|(_, v)| *v)
+    }
+
+    /// Combina con otro; los valores de `other` tienen prioridad.
+    pub fn merged(&self, other: &MaterialOverride) -> MaterialOverride {
+        let mut merged = self.clone();
+        for &(param, value) in &other.values {
+            merged.set(param, value);
+        }
+        merged
+    }
+
+    pub fn apply(&self, material: &mut Material) {
+        for &(param, value) in &self.values {
+            match param {
+                MaterialParam::Diffuse => material.albedo[0] = value,
+                MaterialParam::Specular => material.albedo[1] = value,
+                MaterialParam::Reflectivity => material.albedo[2] = value,
+                MaterialParam::Transparency => material.albedo[3] = value,
+                MaterialParam::Shininess => material.specular = value,
+                MaterialParam::RefractiveIndex => material.refractive_index = value,
+            }
+        }
+    }
+}
 
 #[derive(Clone)]
 #[derive(Debug)]
@@ -10,6 +82,8 @@ pub struct Material {
     pub specular: f32,
     pub refractive_index: f32,
     pub texture: Option<Rc<Texture>>,
+    pub procedural: Option<Rc<dyn ProceduralTexture>>,
+    pub mapping: Mapping,
 }
 
 impl Material {
@@ -26,9 +100,21 @@ impl Material {
             specular,
             refractive_index,
             texture,
+            procedural: None,
+            mapping: Mapping::Uv,
         }
     }
 
+    pub fn with_procedural(mut self, procedural: Rc<dyn ProceduralTexture>) -> Self {
+        self.procedural = Some(procedural);
+        self
+    }
+
+    pub fn with_mapping(mut self, mapping: Mapping) -> Self {
+        self.mapping = mapping;
+        self
+    }
+
     pub fn default() -> Self {
         Material {
             albedo: [0.0, 0.0, 0.0, 0.0],
@@ -36,6 +122,8 @@ impl Material {
             specular: 50.0,
             refractive_index: 1.0,
             texture: None,
+            procedural: None,
+            mapping: Mapping::Uv,
         }
     }
 
@@ -46,4 +134,56 @@ impl Material {
             self.diffuse_color
         }
     }
+
+    // La textura procedural, si existe, tiene prioridad sobre la imagen.
+    pub fn get_color_at(&self, u: f32, v: f32, local_point: &Vec3) -> [u8; 3] {
+        if let Some(procedural) = &self.procedural {
+            procedural.sample(local_point)
+        } else {
+            self.get_texture_color(u, v)
+        }
+    }
+
+    pub fn get_surface_color(&self, u: f32, v: f32, point: &Vec3, local_point: &Vec3, normal: &Vec3) -> [u8; 3] {
+        match self.mapping {
+            Mapping::Uv => self.get_color_at(u, v, local_point),
+            Mapping::Solid => self.get_color_at(u, v, point),
+            Mapping::Triplanar { scale, sharpness } => {
+                if let Some(procedural) = &self.procedural {
+                    procedural.sample(&(point * scale))
+                } else if let Some(texture) = &self.texture {
+                    triplanar(texture, point, normal, scale, sharpness)
+                } else {
+                    self.diffuse_color
+                }
+            }
+        }
+    }
+}
+
+fn triplanar(texture: &Texture, point: &Vec3, normal: &Vec3, scale: f32, sharpness: f32) -> [u8; 3] {
+    let p = point * scale;
+    let weights = Vec3::new(
+        normal.x.abs().powf(sharpness),
+        normal.y.abs().powf(sharpness),
+        normal.z.abs().powf(sharpness),
+    );
+    let total = weights.x + weights.y + weights.z;
+    if total <= f32::EPSILON {
+        return texture.get_color_wrapped(p.x, p.y);
+    }
+
+    let samples = [
+        (weights.x / total, texture.get_color_wrapped(p.z, 1.0 - p.y)),
+        (weights.y / total, texture.get_color_wrapped(p.x, p.z)),
+        (weights.z / total, texture.get_color_wrapped(p.x, 1.0 - p.y)),
+    ];
+
+    let mut color = [0.0f32; 3];
+    for (weight, sample) in samples {
+        for channel in 0..3 {
+            color[channel] += sample[channel] as f32 * weight;
+        }
+    }
+    [color[0] as u8, color[1] as u8, color[2] as u8]
 }
@@ -0,0 +1,6 @@
+/// Interpolación de Hermite entre 0 y 1 cuando `x` va de `edge0` a `edge1`;
+/// admite `edge0 > edge1` para una rampa descendente.
+pub fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
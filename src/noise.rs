@@ -0,0 +1,198 @@
+use nalgebra_glm::Vec3;
+
+// Ruido procedural evaluado en 3D. Todas las funciones son deterministas:
+// el mismo punto y la misma semilla dan siempre el mismo valor.
+
+const GRADIENTS: [[f32; 3]; 12] = [
+    [1.0, 1.0, 0.0], [-1.0, 1.0, 0.0], [1.0, -1.0, 0.0], [-1.0, -1.0, 0.0],
+    [1.0, 0.0, 1.0], [-1.0, 0.0, 1.0], [1.0, 0.0, -1.0], [-1.0, 0.0, -1.0],
+    [0.0, 1.0, 1.0], [0.0, -1.0, 1.0], [0.0, 1.0, -1.0], [0.0, -1.0, -1.0],
+];
+
+fn hash(x: i32, y: i32, z: i32, seed: u32) -> u32 {
+    let mut h = seed
+        ^ (x as u32).wrapping_mul(0x8da6_b343)
+        ^ (y as u32).wrapping_mul(0xd816_3841)
+        ^ (z as u32).wrapping_mul(0xcb1a_b31f);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x2c1b_3c6d);
+    h ^= h >> 12;
+    h = h.wrapping_mul(0x297a_2d39);
+    h ^ (h >> 15)
+}
+
+fn hash_to_unit(h: u32) -> f32 {
+    (h & 0x00ff_ffff) as f32 / 16_777_216.0
+}
+
+fn gradient_dot(h: u32, x: f32, y: f32, z: f32) -> f32 {
+    let g = GRADIENTS[(h % 12) as usize];
+    g[0] * x + g[1] * y + g[2] * z
+}
+
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// Ruido de Perlin clásico, aproximadamente en [-1, 1].
+pub fn perlin(point: &Vec3, seed: u32) -> f32 {
+    let xi = point.x.floor() as i32;
+    let yi = point.y.floor() as i32;
+    let zi = point.z.floor() as i32;
+    let xf = point.x - xi as f32;
+    let yf = point.y - yi as f32;
+    let zf = point.z - zi as f32;
+
+    let u = fade(xf);
+    let v = fade(yf);
+    let w = fade(zf);
+
+    let corner = |dx: i32, dy: i32, dz: i32| {
+        gradient_dot(
+            hash(xi + dx, yi + dy, zi + dz, seed),
+            xf - dx as f32,
+            yf - dy as f32,
+            zf - dz as f32,
+        )
+    };
+
+    let x00 = lerp(corner(0, 0, 0), corner(1, 0, 0), u);
+    let x10 = lerp(corner(0, 1, 0), corner(1, 1, 0), u);
+    let x01 = lerp(corner(0, 0, 1), corner(1, 0, 1), u);
+    let x11 = lerp(corner(0, 1, 1), corner(1, 1, 1), u);
+
+    lerp(lerp(x00, x10, v), lerp(x01, x11, v), w)
+}
+
+/// Ruido simplex 3D, aproximadamente en [-1, 1].
+pub fn simplex(point: &Vec3, seed: u32) -> f32 {
+    const F3: f32 = 1.0 / 3.0;
+    const G3: f32 = 1.0 / 6.0;
+
+    let s = (point.x + point.y + point.z) * F3;
+    let i = (point.x + s).floor() as i32;
+    let j = (point.y + s).floor() as i32;
+    let k = (point.z + s).floor() as i32;
+
+    let t = (i + j + k) as f32 * G3;
+    let x0 = point.x - (i as f32 - t);
+    let y0 = point.y - (j as f32 - t);
+    let z0 = point.z - (k as f32 - t);
+
+    let (i1, j1, k1, i2, j2, k2) = if x0 >= y0 {
+        if y0 >= z0 {
+            (1, 0, 0, 1, 1, 0)
+        } else if x0 >= z0 {
+            (1, 0, 0, 1, 0, 1)
+        } else {
+            (0, 0, 1, 1, 0, 1)
+        }
+    } else if y0 < z0 {
+        (0, 0, 1, 0, 1, 1)
+    } else if x0 < z0 {
+        (0, 1, 0, 0, 1, 1)
+    } else {
+        (0, 1, 0, 1, 1, 0)
+    };
+
+    let offsets = [
+        (0, 0, 0, 0.0),
+        (i1, j1, k1, G3),
+        (i2, j2, k2, 2.0 * G3),
+        (1, 1, 1, 3.0 * G3),
+    ];
+
+    let mut total = 0.0;
+    for (di, dj, dk, g) in offsets {
+        let x = x0 - di as f32 + g;
+        let y = y0 - dj as f32 + g;
+        let z = z0 - dk as f32 + g;
+        let falloff = 0.6 - x * x - y * y - z * z;
+        if falloff > 0.0 {
+            let falloff = falloff * falloff;
+            total += falloff * falloff * gradient_dot(hash(i + di, j + dj, k + dk, seed), x, y, z);
+        }
+    }
+
+    32.0 * total
+}
+
+/// Movimiento browniano fraccional: suma de octavas de Perlin.
+pub fn fbm(point: &Vec3, octaves: u32, lacunarity: f32, gain: f32, seed: u32) -> f32 {
+    let mut total = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0;
+    let mut norm = 0.0;
+
+    for octave in 0..octaves {
+        total += amplitude * perlin(&(point * frequency), seed.wrapping_add(octave));
+        norm += amplitude;
+        amplitude *= gain;
+        frequency *= lacunarity;
+    }
+
+    if norm > 0.0 {
+        total / norm
+    } else {
+        0.0
+    }
+}
+
+/// Turbulencia: fBm sobre el valor absoluto del ruido, en [0, 1].
+pub fn turbulence(point: &Vec3, octaves: u32, seed: u32) -> f32 {
+    let mut total = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0;
+    let mut norm = 0.0;
+
+    for octave in 0..octaves {
+        total += amplitude * perlin(&(point * frequency), seed.wrapping_add(octave)).abs();
+        norm += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+
+    if norm > 0.0 {
+        total / norm
+    } else {
+        0.0
+    }
+}
+
+/// Ruido celular de Worley. Devuelve las distancias al punto característico
+/// más cercano (F1) y al segundo más cercano (F2).
+pub fn worley(point: &Vec3, seed: u32) -> (f32, f32) {
+    let xi = point.x.floor() as i32;
+    let yi = point.y.floor() as i32;
+    let zi = point.z.floor() as i32;
+
+    let mut f1 = f32::INFINITY;
+    let mut f2 = f32::INFINITY;
+
+    for dz in -1..=1 {
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let (cx, cy, cz) = (xi + dx, yi + dy, zi + dz);
+                let h = hash(cx, cy, cz, seed);
+                let feature = Vec3::new(
+                    cx as f32 + hash_to_unit(h),
+                    cy as f32 + hash_to_unit(hash(cx, cy, cz, h)),
+                    cz as f32 + hash_to_unit(hash(cx, cy, cz, h ^ 0x9e37_79b9)),
+                );
+                let distance = (feature - point).magnitude();
+                if distance < f1 {
+                    f2 = f1;
+                    f1 = distance;
+                } else if distance < f2 {
+                    f2 = distance;
+                }
+            }
+        }
+    }
+
+    (f1, f2)
+}
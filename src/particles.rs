@@ -0,0 +1,353 @@
+use nalgebra_glm::Vec3;
+
+use crate::material::Material;
+use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::rng::Rng;
+
+// Paso con el que `prewarm` simula; en el bucle principal los pasos los
+// reparte `FixedTimestep`, así el estado depende solo de la semilla y del
+// número de pasos, no de los cuadros por segundo.
+const FIXED_STEP: f32 = 1.0 / 60.0;
+const GRID_RESOLUTION: usize = 16;
+
+#[derive(Debug, Clone, Copy)]
+pub enum SpawnVolume {
+    Box { min: Vec3, max: Vec3 },
+}
+
+impl SpawnVolume {
+    fn sample(&self, rng: &mut Rng) -> Vec3 {
+        match *self {
+            SpawnVolume::Box { min, max } => Vec3::new(
+                rng.range(min.x, max.x),
+                rng.range(min.y, max.y),
+                rng.range(min.z, max.z),
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Emitter {
+    pub volume: SpawnVolume,
+    // Partículas por segundo.
+    pub rate: f32,
+    pub velocity: Vec3,
+    pub velocity_jitter: Vec3,
+    pub lifetime: f32,
+    pub lifetime_jitter: f32,
+    pub radius: f32,
+    pub max_particles: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Particle {
+    pub position: Vec3,
+    pub velocity: Vec3,
+    pub age: f32,
+    pub lifetime: f32,
+    pub radius: f32,
+    // Fase del balanceo lateral, distinta para cada partícula.
+    phase: f32,
+}
+
+/// Partículas dibujadas como discos siempre orientados hacia el origen del
+/// rayo.
+pub struct ParticleSystem {
+    pub emitter: Emitter,
+    pub wind: Vec3,
+    pub gust_strength: f32,
+    pub flutter: f32,
+    pub gravity: Vec3,
+    // Las partículas por debajo de esta altura desaparecen.
+    pub floor: f32,
+    pub material: Material,
+    particles: Vec<Particle>,
+    rng: Rng,
+    time: f32,
+    spawn_accumulator: f32,
+    grid: ParticleGrid,
+}
+
+impl ParticleSystem {
+    pub fn new(emitter: Emitter, material: Material, seed: u64) -> Self {
+        ParticleSystem {
+            emitter,
+            wind: Vec3::zeros(),
+            gust_strength: 0.0,
+            flutter: 0.0,
+            gravity: Vec3::zeros(),
+            floor: f32::NEG_INFINITY,
+            material,
+            particles: Vec::new(),
+            rng: Rng::new(seed),
+            time: 0.0,
+            spawn_accumulator: 0.0,
+            grid: ParticleGrid::default(),
+        }
+    }
+
+    /// Nieve cayendo desde la caja `min`-`max` hasta `floor`.
+    pub fn snowfall(min: Vec3, max: Vec3, floor: f32, seed: u64) -> Self {
+        let emitter = Emitter {
+            volume: SpawnVolume::Box { min: Vec3::new(min.x, max.y, min.z), max },
+            rate: 180.0,
+            velocity: Vec3::new(0.0, -1.2, 0.0),
+            velocity_jitter: Vec3::new(0.1, 0.3, 0.1),
+            lifetime: (max.y - floor) / 1.2 + 2.0,
+            lifetime_jitter: 1.0,
+            radius: 0.04,
+            max_particles: 3000,
+        };
+        let material = Material::new([0.8, 0.4, 0.0, 0.0], [245, 248, 255], 20.0, 1.0, None);
+        let mut system = ParticleSystem::new(emitter, material, seed);
+        system.wind = Vec3::new(0.3, 0.0, 0.1);
+        system.gust_strength = 0.4;
+        system.flutter = 0.25;
+        system.floor = floor;
+        system
+    }
+
+    /// Simula `seconds` de golpe, p. ej. para que la nieve ya llene la escena al empezar.
+    pub fn prewarm(&mut self, seconds: f32) {
+        for _ in 0..(seconds / FIXED_STEP).round() as u32 {
+            self.step(FIXED_STEP);
+        }
+        self.grid = ParticleGrid::build(&self.particles);
+    }
+
+    /// Avanza un paso de `dt` segundos; quien llama reparte el tiempo real en
+    /// pasos fijos.
+    pub fn update(&mut self, dt: f32) {
+        self.step(dt);
+        self.grid = ParticleGrid::build(&self.particles);
+    }
+
+    fn spawn(&mut self) {
+        let e = &self.emitter;
+        let position = e.volume.sample(&mut self.rng);
+        let velocity = e.velocity
+            + Vec3::new(
+                e.velocity_jitter.x * self.rng.signed(),
+                e.velocity_jitter.y * self.rng.signed(),
+                e.velocity_jitter.z * self.rng.signed(),
+            );
+        let lifetime = (e.lifetime + e.lifetime_jitter * self.rng.signed()).max(FIXED_STEP);
+        let radius = e.radius * self.rng.range(0.6, 1.4);
+        let phase = self.rng.range(0.0, 2.0 * std::f32::consts::PI);
+        self.particles.push(Particle { position, velocity, age: 0.0, lifetime, radius, phase });
+    }
+
+    fn step(&mut self, dt: f32) {
+        self.time += dt;
+
+        self.spawn_accumulator += self.emitter.rate * dt;
+        while self.spawn_accumulator >= 1.0 {
+            self.spawn_accumulator -= 1.0;
+            if self.particles.len() < self.emitter.max_particles {
+                self.spawn();
+            }
+        }
+
+        let gust = self.wind * (1.0 + self.gust_strength * (self.time * 0.7).sin());
+        for p in &mut self.particles {
+            p.age += dt;
+            p.velocity += self.gravity * dt;
+            let sway = Vec3::new((self.time * 1.7 + p.phase).sin(), 0.0, (self.time * 1.3 + p.phase).cos()) * self.flutter;
+            p.position += (p.velocity + gust + sway) * dt;
+        }
+
+        let floor = self.floor;
+        self.particles.retain(|p| p.age < p.lifetime && p.position.y > floor);
+    }
+
+    fn hit_particle(&self, particle: &Particle, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<f32> {
+        let t = (particle.position - ray_origin).dot(ray_direction) / ray_direction.dot(ray_direction);
+        if t < 0.0 {
+            return None;
+        }
+        let closest = ray_origin + ray_direction * t - particle.position;
+        (closest.magnitude_squared() <= particle.radius * particle.radius).then_some(t)
+    }
+}
+
+impl RayIntersect for ParticleSystem {
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
+        let mut best: Option<(f32, usize)> = None;
+        self.grid.traverse(ray_origin, ray_direction, |indices, t_exit| {
+            for &index in indices {
+                if let Some(t) = self.hit_particle(&self.particles[index], ray_origin, ray_direction) {
+                    if best.is_none_or(|(d, _)| t < d) {
+                        best = Some((t, index));
+                    }
+                }
+            }
+            // Seguir solo si no hay impacto antes de salir de la celda.
+            best.is_none_or(|(d, _)| d > t_exit)
+        });
+
+        let (distance, index) = match best {
+            Some(hit) => hit,
+            None => return Intersect::empty(),
+        };
+        let particle = &self.particles[index];
+        let point = ray_origin + ray_direction * distance;
+        let mut intersect = Intersect::new(point, -ray_direction, distance, self.material.clone(), None, None);
+        intersect.local_point = point - particle.position;
+        intersect
+    }
+}
+
+// Rejilla uniforme sobre la caja que contiene las partículas, para no probar
+// todas contra cada rayo.
+#[derive(Default)]
+struct ParticleGrid {
+    min: Vec3,
+    cell_size: Vec3,
+    cells: Vec<Vec<usize>>,
+}
+
+impl ParticleGrid {
+    fn build(particles: &[Particle]) -> Self {
+        if particles.is_empty() {
+            return ParticleGrid::default();
+        }
+
+        let mut min = Vec3::repeat(f32::INFINITY);
+        let mut max = Vec3::repeat(f32::NEG_INFINITY);
+        for p in particles {
+            min = min.inf(&(p.position - Vec3::repeat(p.radius)));
+            max = max.sup(&(p.position + Vec3::repeat(p.radius)));
+        }
+        let extent = (max - min).map(|e| e.max(1e-3));
+        let cell_size = extent / GRID_RESOLUTION as f32;
+
+        let mut cells = vec![Vec::new(); GRID_RESOLUTION * GRID_RESOLUTION * GRID_RESOLUTION];
+        let cell_of = |v: f32, lo: f32, size: f32| (((v - lo) / size) as usize).min(GRID_RESOLUTION - 1);
+        for (index, p) in particles.iter().enumerate() {
+            let lo = p.position - Vec3::repeat(p.radius);
+            let hi = p.position + Vec3::repeat(p.radius);
+            for z in cell_of(lo.z, min.z, cell_size.z)..=cell_of(hi.z, min.z, cell_size.z) {
+                for y in cell_of(lo.y, min.y, cell_size.y)..=cell_of(hi.y, min.y, cell_size.y) {
+                    for x in cell_of(lo.x, min.x, cell_size.x)..=cell_of(hi.x, min.x, cell_size.x) {
+                        cells[(z * GRID_RESOLUTION + y) * GRID_RESOLUTION + x].push(index);
+                    }
+                }
+            }
+        }
+
+        ParticleGrid { min, cell_size, cells }
+    }
+
+    // Recorre las celdas que atraviesa el rayo (DDA 3D) en orden; `visit`
+    // recibe las partículas de la celda y la distancia de salida, y devuelve
+    // si hay que continuar.
+    fn traverse<F>(&self, ray_origin: &Vec3, ray_direction: &Vec3, mut visit: F)
+    where
+        F: FnMut(&[usize], f32) -> bool,
+    {
+        if self.cells.is_empty() {
+            return;
+        }
+        let max = self.min + self.cell_size * GRID_RESOLUTION as f32;
+
+        // Entrada y salida de la caja de la rejilla
+        let mut t_enter: f32 = 0.0;
+        let mut t_leave = f32::INFINITY;
+        for axis in 0..3 {
+            let inv = 1.0 / ray_direction[axis];
+            let mut t0 = (self.min[axis] - ray_origin[axis]) * inv;
+            let mut t1 = (max[axis] - ray_origin[axis]) * inv;
+            if t0 > t1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            t_enter = t_enter.max(t0);
+            t_leave = t_leave.min(t1);
+        }
+        if t_enter > t_leave {
+            return;
+        }
+
+        let start = ray_origin + ray_direction * t_enter;
+        let mut cell = [0i32; 3];
+        let mut step = [0i32; 3];
+        let mut t_max = [f32::INFINITY; 3];
+        let mut t_delta = [f32::INFINITY; 3];
+        for axis in 0..3 {
+            let c = ((start[axis] - self.min[axis]) / self.cell_size[axis]).floor() as i32;
+            cell[axis] = c.clamp(0, GRID_RESOLUTION as i32 - 1);
+            if ray_direction[axis] > 0.0 {
+                step[axis] = 1;
+                let boundary = self.min[axis] + (cell[axis] + 1) as f32 * self.cell_size[axis];
+                t_max[axis] = (boundary - ray_origin[axis]) / ray_direction[axis];
+                t_delta[axis] = self.cell_size[axis] / ray_direction[axis];
+            } else if ray_direction[axis] < 0.0 {
+                step[axis] = -1;
+                let boundary = self.min[axis] + cell[axis] as f32 * self.cell_size[axis];
+                t_max[axis] = (boundary - ray_origin[axis]) / ray_direction[axis];
+                t_delta[axis] = -self.cell_size[axis] / ray_direction[axis];
+            }
+        }
+
+        loop {
+            let index = (cell[2] as usize * GRID_RESOLUTION + cell[1] as usize) * GRID_RESOLUTION + cell[0] as usize;
+            let axis = if t_max[0] < t_max[1] {
+                if t_max[0] < t_max[2] { 0 } else { 2 }
+            } else if t_max[1] < t_max[2] {
+                1
+            } else {
+                2
+            };
+            let t_exit = t_max[axis].min(t_leave);
+
+            let indices = &self.cells[index];
+            if !indices.is_empty() && !visit(indices, t_exit) {
+                return;
+            }
+
+            cell[axis] += step[axis];
+            if cell[axis] < 0 || cell[axis] >= GRID_RESOLUTION as i32 || t_max[axis] > t_leave {
+                return;
+            }
+            t_max[axis] += t_delta[axis];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snowfall(seed: u64) -> ParticleSystem {
+        ParticleSystem::snowfall(Vec3::new(-2.0, 0.0, -2.0), Vec3::new(2.0, 4.0, 2.0), 0.0, seed)
+    }
+
+    #[test]
+    fn same_seed_and_steps_give_the_same_particles() {
+        let mut a = snowfall(7);
+        let mut b = snowfall(7);
+        a.prewarm(1.0);
+        b.prewarm(1.0);
+        for _ in 0..120 {
+            a.update(FIXED_STEP);
+            b.update(FIXED_STEP);
+        }
+        assert!(!a.particles.is_empty());
+        assert_eq!(a.particles, b.particles);
+
+        let mut c = snowfall(8);
+        c.prewarm(1.0);
+        assert_ne!(a.particles[0], c.particles[0]);
+    }
+
+    #[test]
+    fn update_steps_once_per_call() {
+        let mut system = snowfall(1);
+        // Un paso más corto que FIXED_STEP también avanza la simulación
+        system.update(FIXED_STEP * 0.5);
+        assert_eq!(system.time, FIXED_STEP * 0.5);
+        system.update(0.1);
+        assert_eq!(system.time, FIXED_STEP * 0.5 + 0.1);
+        assert_eq!(system.particles.len(), (system.emitter.rate * system.time) as usize);
+    }
+}
@@ -0,0 +1,194 @@
+use nalgebra_glm::Vec3;
+
+use crate::camera::Camera;
+use crate::framebuffer::Framebuffer;
+use crate::ray_intersect::Intersect;
+use crate::scene::Scene;
+
+/// Objeto bajo un píxel, con los datos del impacto en el momento del clic.
+#[derive(Debug, Clone)]
+pub struct Pick {
+    pub index: usize,
+    pub name: String,
+    pub intersect: Intersect,
+}
+
+/// Lanza un rayo por el píxel (x, y) de una imagen de `width` x `height`.
+pub fn pick(scene: &Scene, camera: &Camera, x: f32, y: f32, width: usize, height: usize) -> Option<Pick> {
+    let direction = camera.primary_ray(x, y, width as f32, height as f32);
+    scene
+        .closest(&camera.eye, &direction)
+        .map(|(index, intersect)| Pick { index, name: scene.object_name(index), intersect })
+}
+
+impl Pick {
+    /// Índice actual del objeto: puede cambiar al reconstruir la escena si se
+    /// ocultan o añaden nodos, así que se busca de nuevo por nombre.
+    pub fn current_index(&self, scene: &Scene) -> Option<usize> {
+        if self.index < scene.objects.len() + scene.particles.len() && scene.object_name(self.index) == self.name {
+            return Some(self.index);
+        }
+        scene.object_names.iter().position(|n| *n == self.name)
+    }
+
+    /// Resumen del impacto, una línea por dato.
+    pub fn describe(&self) -> Vec<String> {
+        let i = &self.intersect;
+        let material = &i.material;
+        let uv = match (i.u, i.v) {
+            (Some(u), Some(v)) => format!("{:.3} {:.3}", u, v),
+            _ => "-".to_string(),
+        };
+        let texture = match (&material.texture, &material.procedural) {
+            (Some(texture), _) => texture.path().to_string(),
+            (None, Some(_)) => "procedural".to_string(),
+            (None, None) => "-".to_string(),
+        };
+        vec![
+            format!("objeto {} (#{})", self.name, self.index),
+            format!("distancia {:.3}", i.distance),
+            format!("punto {:.3} {:.3} {:.3}", i.point.x, i.point.y, i.point.z),
+            format!("normal {:.3} {:.3} {:.3}", i.normal.x, i.normal.y, i.normal.z),
+            format!("uv {}", uv),
+            format!(
+                "albedo {:.2} {:.2} {:.2} {:.2}",
+                material.albedo[0], material.albedo[1], material.albedo[2], material.albedo[3]
+            ),
+            format!(
+                "color {} {} {}  specular {:.1}  ior {:.2}",
+                material.diffuse_color[0], material.diffuse_color[1], material.diffuse_color[2],
+                material.specular, material.refractive_index
+            ),
+            format!("textura {}", texture),
+            format!("mapeo {:?}", material.mapping),
+        ]
+    }
+}
+
+/// Última máscara calculada por `selection_mask`; se reutiliza mientras no
+/// cambien la cámara, la escena, la resolución ni el objeto.
+#[derive(Default)]
+pub struct SelectionCache {
+    key: Option<MaskKey>,
+    mask: Vec<bool>,
+}
+
+#[derive(PartialEq)]
+struct MaskKey {
+    index: usize,
+    width: usize,
+    height: usize,
+    eye: Vec3,
+    center: Vec3,
+    up: Vec3,
+    fov: f32,
+    revision: u64,
+}
+
+impl SelectionCache {
+    pub fn mask(&mut self, scene: &Scene, camera: &Camera, index: usize, width: usize, height: usize) -> &[bool] {
+        let key = MaskKey {
+            index,
+            width,
+            height,
+            eye: camera.eye,
+            center: camera.center,
+            up: camera.up,
+            fov: camera.fov,
+            revision: scene.revision(),
+        };
+        // Las partículas se mueven en cada paso de simulación
+        let particles = index >= scene.objects.len();
+        if particles || self.key.as_ref() != Some(&key) {
+            self.mask = selection_mask(scene, camera, index, width, height);
+            self.key = Some(key);
+        }
+        &self.mask
+    }
+}
+
+/// Píxeles donde el objeto `index` es lo primero que se ve. Solo se lanzan
+/// rayos dentro de su caja proyectada en pantalla, si la tiene.
+pub fn selection_mask(scene: &Scene, camera: &Camera, index: usize, width: usize, height: usize) -> Vec<bool> {
+    let mut mask = vec![false; width * height];
+    let (w, h) = (width as f32, height as f32);
+
+    let mut region = (0, 0, width, height);
+    if let Some(bounds) = scene.object_bounds(index) {
+        let corners: Option<Vec<(f32, f32)>> = (0..8)
+            .map(|c| {
+                let corner = Vec3::new(
+                    if c & 1 == 0 { bounds.min.x } else { bounds.max.x },
+                    if c & 2 == 0 { bounds.min.y } else { bounds.max.y },
+                    if c & 4 == 0 { bounds.min.z } else { bounds.max.z },
+                );
+                camera.project(&corner, w, h)
+            })
+            .collect();
+        // Si alguna esquina queda detrás de la cámara se recorre toda la pantalla
+        if let Some(corners) = corners {
+            let (min_x, max_x, min_y, max_y) = corners.iter().fold(
+                (f32::INFINITY, f32::NEG_INFINITY, f32::INFINITY, f32::NEG_INFINITY),
+                |(x0, x1, y0, y1), (x, y)| (x0.min(*x), x1.max(*x), y0.min(*y), y1.max(*y)),
+            );
+            let clamp_x = |v: f32| (v.max(0.0) as usize).min(width);
+            let clamp_y = |v: f32| (v.max(0.0) as usize).min(height);
+            region = (clamp_x(min_x.floor()), clamp_y(min_y.floor()), clamp_x(max_x.ceil() + 1.0), clamp_y(max_y.ceil() + 1.0));
+        }
+    }
+
+    // Las partículas no tapan un objeto seleccionado; si no, cada copo
+    // dejaría un agujero con borde dentro de la silueta
+    let selected_particles = index >= scene.objects.len();
+    let (x0, y0, x1, y1) = region;
+    for y in y0..y1 {
+        for x in x0..x1 {
+            let direction = camera.primary_ray(x as f32, y as f32, w, h);
+            let hit = if selected_particles {
+                scene.closest(&camera.eye, &direction).map(|(hit, _)| hit)
+            } else {
+                closest_object(scene, &camera.eye, &direction)
+            };
+            mask[y * width + x] = hit == Some(index);
+        }
+    }
+    mask
+}
+
+// Índice del objeto más cercano sin contar las partículas.
+fn closest_object(scene: &Scene, origin: &Vec3, direction: &Vec3) -> Option<usize> {
+    scene.count_ray();
+    let mut closest = None;
+    let mut zbuffer = f32::INFINITY;
+    for (index, object) in scene.objects.iter().enumerate() {
+        let i = object.ray_intersect(origin, direction);
+        if i.is_intersecting && i.distance < zbuffer {
+            zbuffer = i.distance;
+            closest = Some(index);
+        }
+    }
+    closest
+}
+
+/// Pinta con el color actual el borde de la máscara, con `thickness` píxeles
+/// hacia fuera.
+pub fn draw_outline(framebuffer: &mut Framebuffer, mask: &[bool], thickness: usize) {
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    let t = thickness as isize;
+    for y in 0..height {
+        for x in 0..width {
+            if mask[y * width + x] {
+                continue;
+            }
+            let near_selection = (-t..=t).any(|dy| {
+                (-t..=t).any(|dx| {
+                    let (nx, ny) = (x as isize + dx, y as isize + dy);
+                    nx >= 0 && ny >= 0 && (nx as usize) < width && (ny as usize) < height && mask[ny as usize * width + nx as usize]
+                })
+            });
+            if near_selection {
+                framebuffer.point(x, y);
+            }
+        }
+    }
+}
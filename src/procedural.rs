@@ -0,0 +1,437 @@
+use std::fmt::Debug;
+use nalgebra_glm::Vec3;
+use crate::noise;
+
+/// Textura evaluada en un punto 3D (espacio del objeto) en lugar de una imagen.
+pub trait ProceduralTexture: Debug {
+    fn sample(&self, point: &Vec3) -> [u8; 3];
+}
+
+fn mix(a: [u8; 3], b: [u8; 3], t: f32) -> [u8; 3] {
+    let t = t.clamp(0.0, 1.0);
+    [
+        (a[0] as f32 + (b[0] as f32 - a[0] as f32) * t) as u8,
+        (a[1] as f32 + (b[1] as f32 - a[1] as f32) * t) as u8,
+        (a[2] as f32 + (b[2] as f32 - a[2] as f32) * t) as u8,
+    ]
+}
+
+/// Rampa de colores: interpola linealmente entre paradas ordenadas en [0, 1].
+#[derive(Debug, Clone)]
+pub struct ColorRamp {
+    stops: Vec<(f32, [u8; 3])>,
+}
+
+impl ColorRamp {
+    pub fn new(mut stops: Vec<(f32, [u8; 3])>) -> Self {
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        ColorRamp { stops }
+    }
+
+    pub fn two(a: [u8; 3], b: [u8; 3]) -> Self {
+        ColorRamp::new(vec![(0.0, a), (1.0, b)])
+    }
+
+    pub fn eval(&self, t: f32) -> [u8; 3] {
+        let (first, last) = match (self.stops.first(), self.stops.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return [0, 0, 0],
+        };
+        if t <= first.0 {
+            return first.1;
+        }
+        if t >= last.0 {
+            return last.1;
+        }
+        for pair in self.stops.windows(2) {
+            let (t0, c0) = pair[0];
+            let (t1, c1) = pair[1];
+            if t <= t1 {
+                let span = (t1 - t0).max(f32::EPSILON);
+                return mix(c0, c1, (t - t0) / span);
+            }
+        }
+        last.1
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum NoiseKind {
+    Perlin,
+    Simplex,
+    Fbm { octaves: u32, lacunarity: f32, gain: f32 },
+    Turbulence { octaves: u32 },
+}
+
+impl NoiseKind {
+    /// Valor del ruido remapeado a [0, 1].
+    pub fn eval(&self, point: &Vec3, seed: u32) -> f32 {
+        let value = match *self {
+            NoiseKind::Perlin => noise::perlin(point, seed) * 0.5 + 0.5,
+            NoiseKind::Simplex => noise::simplex(point, seed) * 0.5 + 0.5,
+            NoiseKind::Fbm { octaves, lacunarity, gain } => {
+                noise::fbm(point, octaves, lacunarity, gain, seed) * 0.5 + 0.5
+            }
+            NoiseKind::Turbulence { octaves } => noise::turbulence(point, octaves, seed),
+        };
+        value.clamp(0.0, 1.0)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NoiseTexture {
+    pub kind: NoiseKind,
+    pub scale: f32,
+    pub seed: u32,
+    pub ramp: ColorRamp,
+}
+
+impl NoiseTexture {
+    pub fn new(kind: NoiseKind, scale: f32, seed: u32, ramp: ColorRamp) -> Self {
+        NoiseTexture { kind, scale, seed, ramp }
+    }
+}
+
+impl ProceduralTexture for NoiseTexture {
+    fn sample(&self, point: &Vec3) -> [u8; 3] {
+        self.ramp.eval(self.kind.eval(&(point * self.scale), self.seed))
+    }
+}
+
+/// Celdas de Worley. `crack_width` > 0 dibuja bordes entre celdas (F2 - F1).
+#[derive(Debug, Clone)]
+pub struct CellTexture {
+    pub scale: f32,
+    pub seed: u32,
+    pub ramp: ColorRamp,
+    pub crack_width: f32,
+    pub crack_color: [u8; 3],
+}
+
+impl CellTexture {
+    pub fn new(scale: f32, seed: u32, ramp: ColorRamp) -> Self {
+        CellTexture {
+            scale,
+            seed,
+            ramp,
+            crack_width: 0.0,
+            crack_color: [0, 0, 0],
+        }
+    }
+
+    pub fn with_cracks(mut self, width: f32, color: [u8; 3]) -> Self {
+        self.crack_width = width;
+        self.crack_color = color;
+        self
+    }
+}
+
+impl ProceduralTexture for CellTexture {
+    fn sample(&self, point: &Vec3) -> [u8; 3] {
+        let (f1, f2) = noise::worley(&(point * self.scale), self.seed);
+        let base = self.ramp.eval(f1);
+        if self.crack_width > 0.0 {
+            let edge = ((f2 - f1) / self.crack_width).clamp(0.0, 1.0);
+            mix(self.crack_color, base, edge)
+        } else {
+            base
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Checker {
+    pub scale: f32,
+    pub even: [u8; 3],
+    pub odd: [u8; 3],
+}
+
+impl Checker {
+    pub fn new(scale: f32, even: [u8; 3], odd: [u8; 3]) -> Self {
+        Checker { scale, even, odd }
+    }
+}
+
+impl ProceduralTexture for Checker {
+    fn sample(&self, point: &Vec3) -> [u8; 3] {
+        let p = point * self.scale;
+        let sum = p.x.floor() as i64 + p.y.floor() as i64 + p.z.floor() as i64;
+        if sum.rem_euclid(2) == 0 {
+            self.even
+        } else {
+            self.odd
+        }
+    }
+}
+
+/// Anillos de madera alrededor de un eje vertical que pasa por `center`,
+/// perturbados con ruido.
+#[derive(Debug, Clone)]
+pub struct Wood {
+    pub center: Vec3,
+    pub scale: f32,
+    pub ring_frequency: f32,
+    pub turbulence: f32,
+    pub light: [u8; 3],
+    pub dark: [u8; 3],
+    pub seed: u32,
+}
+
+impl Wood {
+    pub fn new(light: [u8; 3], dark: [u8; 3]) -> Self {
+        Wood {
+            center: Vec3::zeros(),
+            scale: 1.0,
+            ring_frequency: 8.0,
+            turbulence: 0.15,
+            light,
+            dark,
+            seed: 7,
+        }
+    }
+}
+
+impl ProceduralTexture for Wood {
+    fn sample(&self, point: &Vec3) -> [u8; 3] {
+        let p = (point - self.center) * self.scale;
+        let distortion = noise::fbm(&(p * 2.0), 3, 2.0, 0.5, self.seed) * self.turbulence;
+        let radius = (p.x * p.x + p.z * p.z).sqrt() + distortion;
+        let rings = (radius * self.ring_frequency).fract();
+        mix(self.light, self.dark, rings.powf(3.0))
+    }
+}
+
+/// Vetas de mármol: seno desplazado por turbulencia.
+#[derive(Debug, Clone)]
+pub struct Marble {
+    pub scale: f32,
+    pub vein_frequency: f32,
+    pub turbulence: f32,
+    pub ramp: ColorRamp,
+    pub seed: u32,
+}
+
+impl Marble {
+    pub fn new(ramp: ColorRamp) -> Self {
+        Marble {
+            scale: 1.0,
+            vein_frequency: 4.0,
+            turbulence: 5.0,
+            ramp,
+            seed: 11,
+        }
+    }
+}
+
+impl ProceduralTexture for Marble {
+    fn sample(&self, point: &Vec3) -> [u8; 3] {
+        let p = point * self.scale;
+        let phase = (p.x + p.y * 0.5) * self.vein_frequency
+            + self.turbulence * noise::turbulence(&p, 5, self.seed);
+        self.ramp.eval(phase.sin() * 0.5 + 0.5)
+    }
+}
+
+/// Degradado a lo largo de una dirección, entre `start` y `end`.
+#[derive(Debug, Clone)]
+pub struct Gradient {
+    pub direction: Vec3,
+    pub start: f32,
+    pub end: f32,
+    pub ramp: ColorRamp,
+}
+
+impl Gradient {
+    pub fn new(direction: Vec3, start: f32, end: f32, ramp: ColorRamp) -> Self {
+        Gradient {
+            direction: direction.normalize(),
+            start,
+            end,
+            ramp,
+        }
+    }
+}
+
+impl ProceduralTexture for Gradient {
+    fn sample(&self, point: &Vec3) -> [u8; 3] {
+        let span = self.end - self.start;
+        let t = if span.abs() > f32::EPSILON {
+            (point.dot(&self.direction) - self.start) / span
+        } else {
+            0.0
+        };
+        self.ramp.eval(t)
+    }
+}
+
+/// Nieve con destellos: fBm suave y puntos brillantes dispersos.
+#[derive(Debug, Clone)]
+pub struct SnowSparkle {
+    pub scale: f32,
+    pub sparkle_scale: f32,
+    pub sparkle_density: f32,
+    pub seed: u32,
+}
+
+impl Default for SnowSparkle {
+    fn default() -> Self {
+        SnowSparkle {
+            scale: 1.5,
+            sparkle_scale: 40.0,
+            sparkle_density: 0.08,
+            seed: 3,
+        }
+    }
+}
+
+impl ProceduralTexture for SnowSparkle {
+    fn sample(&self, point: &Vec3) -> [u8; 3] {
+        let base = noise::fbm(&(point * self.scale), 4, 2.0, 0.5, self.seed) * 0.5 + 0.5;
+        let color = mix([205, 215, 232], [245, 248, 255], base);
+        let (f1, _) = noise::worley(&(point * self.sparkle_scale), self.seed ^ 0x5eed);
+        if f1 < self.sparkle_density {
+            [255, 255, 255]
+        } else {
+            color
+        }
+    }
+}
+
+pub fn snow_sparkle() -> SnowSparkle {
+    SnowSparkle::default()
+}
+
+pub fn ice_cracks() -> CellTexture {
+    CellTexture::new(2.5, 19, ColorRamp::two([190, 225, 245], [150, 200, 235]))
+        .with_cracks(0.06, [235, 245, 255])
+}
+
+pub fn stone() -> NoiseTexture {
+    NoiseTexture::new(
+        NoiseKind::Fbm { octaves: 5, lacunarity: 2.0, gain: 0.5 },
+        2.0,
+        23,
+        ColorRamp::new(vec![
+            (0.0, [70, 70, 75]),
+            (0.5, [120, 118, 115]),
+            (1.0, [165, 160, 150]),
+        ]),
+    )
+}
+
+/// Nieve apisonada para el muñeco: Perlin suave, sin destellos.
+pub fn packed_snow() -> NoiseTexture {
+    NoiseTexture::new(
+        NoiseKind::Perlin,
+        3.0,
+        31,
+        ColorRamp::two([215, 222, 235], [250, 252, 255]),
+    )
+}
+
+pub fn frost() -> NoiseTexture {
+    NoiseTexture::new(
+        NoiseKind::Simplex,
+        6.0,
+        37,
+        ColorRamp::two([170, 210, 235], [235, 245, 255]),
+    )
+}
+
+pub fn bark() -> NoiseTexture {
+    NoiseTexture::new(
+        NoiseKind::Turbulence { octaves: 4 },
+        4.0,
+        41,
+        ColorRamp::new(vec![
+            (0.0, [60, 40, 25]),
+            (0.4, [95, 65, 40]),
+            (1.0, [130, 95, 60]),
+        ]),
+    )
+}
+
+/// Anillos de un tocón de `half_width` de lado, centrados en su eje.
+pub fn stump_rings(half_width: f32) -> Wood {
+    Wood {
+        center: Vec3::new(half_width, 0.0, half_width),
+        ring_frequency: 6.0 / half_width,
+        ..Wood::new([200, 160, 110], [120, 80, 45])
+    }
+}
+
+pub fn floor_tiles() -> Checker {
+    Checker::new(2.0, [150, 40, 35], [225, 215, 195])
+}
+
+pub fn white_marble() -> Marble {
+    Marble::new(ColorRamp::new(vec![
+        (0.0, [150, 150, 160]),
+        (0.3, [215, 215, 220]),
+        (1.0, [245, 245, 248]),
+    ]))
+}
+
+/// Laterales del suelo: tierra abajo y nieve en el último tramo.
+pub fn snow_bank(bottom: f32, top: f32) -> Gradient {
+    Gradient::new(
+        Vec3::new(0.0, 1.0, 0.0),
+        bottom,
+        top,
+        ColorRamp::new(vec![
+            (0.0, [90, 70, 55]),
+            (0.7, [120, 100, 85]),
+            (0.85, [225, 232, 242]),
+            (1.0, [245, 248, 255]),
+        ]),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn noise_kinds_stay_in_unit_range() {
+        let kinds = [
+            NoiseKind::Perlin,
+            NoiseKind::Simplex,
+            NoiseKind::Fbm { octaves: 4, lacunarity: 2.0, gain: 0.5 },
+            NoiseKind::Turbulence { octaves: 4 },
+        ];
+        for kind in kinds {
+            for i in 0..64 {
+                let point = Vec3::new(i as f32 * 0.37, i as f32 * 0.11 - 3.0, i as f32 * 0.53);
+                let value = kind.eval(&point, 5);
+                assert!((0.0..=1.0).contains(&value), "{:?} gave {}", kind, value);
+                assert_eq!(value, kind.eval(&point, 5));
+            }
+        }
+    }
+
+    #[test]
+    fn checker_alternates_per_cell() {
+        let checker = Checker::new(1.0, [0, 0, 0], [255, 255, 255]);
+        assert_eq!(checker.sample(&Vec3::new(0.5, 0.5, 0.5)), [0, 0, 0]);
+        assert_eq!(checker.sample(&Vec3::new(1.5, 0.5, 0.5)), [255, 255, 255]);
+        assert_eq!(checker.sample(&Vec3::new(-0.5, 0.5, 0.5)), [255, 255, 255]);
+        assert_eq!(checker.sample(&Vec3::new(1.5, 1.5, 0.5)), [0, 0, 0]);
+    }
+
+    #[test]
+    fn gradient_clamps_outside_its_span() {
+        let gradient = Gradient::new(Vec3::new(0.0, 2.0, 0.0), 1.0, 3.0, ColorRamp::two([0, 0, 0], [200, 100, 50]));
+        assert_eq!(gradient.sample(&Vec3::new(0.0, 0.0, 0.0)), [0, 0, 0]);
+        assert_eq!(gradient.sample(&Vec3::new(0.0, 2.0, 0.0)), [100, 50, 25]);
+        assert_eq!(gradient.sample(&Vec3::new(0.0, 9.0, 0.0)), [200, 100, 50]);
+    }
+
+    #[test]
+    fn wood_rings_are_centered() {
+        let wood = Wood { turbulence: 0.0, ..stump_rings(0.3) };
+        let center = Vec3::new(0.3, 0.0, 0.3);
+        let east = wood.sample(&(center + Vec3::new(0.17, 0.0, 0.0)));
+        assert_eq!(east, wood.sample(&(center + Vec3::new(0.0, 0.4, -0.17))));
+        assert_eq!(east, wood.sample(&(center + Vec3::new(-0.17, 0.0, 0.0))));
+    }
+}
@@ -1,4 +1,6 @@
 use nalgebra_glm::Vec3;
+use std::rc::Rc;
+use crate::geometry::Aabb;
 use crate::material::Material;
 
 #[derive(Debug, Clone)]
@@ -6,6 +8,7 @@ pub struct Intersect {
     pub is_intersecting: bool,
     pub distance: f32,
     pub point: Vec3,
+    pub local_point: Vec3,
     pub normal: Vec3,
     pub material: Material,
     pub u: Option<f32>,
@@ -18,6 +21,7 @@ impl Intersect {
             is_intersecting: false,
             distance: f32::INFINITY,
             point: Vec3::zeros(),
+            local_point: Vec3::zeros(),
             normal: Vec3::zeros(),
             material: Material::new([0.0, 0.0, 0.0, 0.0], [0, 0, 0], 0.0, 0.0, None),
             u: None,
@@ -30,6 +34,7 @@ impl Intersect {
         Intersect {
             is_intersecting: true,
             point,
+            local_point: point,
             normal: normal.normalize(),  
             distance,
             material,
@@ -38,11 +43,71 @@ impl Intersect {
         }
     }
 
+    pub fn surface_color(&self) -> [u8; 3] {
+        self.material.get_surface_color(
+            self.u.unwrap_or(0.0),
+            self.v.unwrap_or(0.0),
+            &self.point,
+            &self.local_point,
+            &self.normal,
+        )
+    }
+}
+
+/// Extremo de un tramo: distancia sobre el rayo y normal hacia fuera del
+/// sólido. El material se resuelve aparte con `surface_at`, solo para el
+/// impacto que se usa.
+#[derive(Debug, Clone, Copy)]
+pub struct Boundary {
+    pub distance: f32,
+    pub normal: Vec3,
+}
+
+/// Tramo del rayo que queda dentro de un sólido. Las distancias pueden ser
+/// negativas.
+#[derive(Debug, Clone, Copy)]
+pub struct Span {
+    pub enter: Boundary,
+    pub exit: Boundary,
 }
 
 pub trait RayIntersect {
     fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect;
+
+    // Caja envolvente en el espacio del objeto; `None` si no tiene una fija.
+    fn bounds(&self) -> Option<Aabb> {
+        None
+    }
+
+    // Tramos ordenados sobre toda la recta del rayo, para las operaciones CSG.
+    // `None` si el objeto no es un sólido cerrado.
+    fn spans(&self, _ray_origin: &Vec3, _ray_direction: &Vec3) -> Option<Vec<Span>> {
+        None
+    }
+
+    // Impacto completo (material, UV) en un extremo de `spans` a `distance`.
+    fn surface_at(&self, _ray_origin: &Vec3, _ray_direction: &Vec3, _distance: f32) -> Option<Intersect> {
+        None
+    }
 }
 
 
 
+
+impl<T: RayIntersect + ?Sized> RayIntersect for Rc<T> {
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
+        (**self).ray_intersect(ray_origin, ray_direction)
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        (**self).bounds()
+    }
+
+    fn spans(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<Vec<Span>> {
+        (**self).spans(ray_origin, ray_direction)
+    }
+
+    fn surface_at(&self, ray_origin: &Vec3, ray_direction: &Vec3, distance: f32) -> Option<Intersect> {
+        (**self).surface_at(ray_origin, ray_direction, distance)
+    }
+}
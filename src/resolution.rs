@@ -0,0 +1,137 @@
+use std::time::Duration;
+
+use crate::framebuffer::UpscaleFilter;
+
+// Un cuadro reducido tiene la mitad de píxeles en cada eje.
+const FULL_RES_COST: u32 = 4;
+// Fracción del presupuesto por debajo de la cual se deja de estar pasado de
+// presupuesto; entre ella y el presupuesto se mantiene el estado anterior.
+const RECOVER_FRACTION: f32 = 0.8;
+
+/// Decide cada cuadro si renderizar a resolución reducida: mientras la
+/// cámara se mueve y, si la resolución completa no cabe en el presupuesto,
+/// también unos cuadros después de pararse. Con la vista quieta se vuelve a
+/// la resolución completa.
+#[derive(Debug, Clone)]
+pub struct DynamicResolution {
+    pub enabled: bool,
+    pub frame_budget: Duration,
+    // Cuadros quietos antes de refinar a resolución completa.
+    pub refine_after: u32,
+    pub filter: UpscaleFilter,
+    still_frames: u32,
+    over_budget: bool,
+    low_res: bool,
+}
+
+impl Default for DynamicResolution {
+    fn default() -> Self {
+        DynamicResolution {
+            enabled: true,
+            frame_budget: Duration::from_millis(50),
+            refine_after: 3,
+            filter: UpscaleFilter::EdgeAware,
+            still_frames: 0,
+            over_budget: false,
+            low_res: false,
+        }
+    }
+}
+
+impl DynamicResolution {
+    /// Llamar al inicio de cada cuadro; devuelve si toca resolución reducida.
+    pub fn begin_frame(&mut self, camera_moved: bool) -> bool {
+        if camera_moved {
+            self.still_frames = 0;
+        } else {
+            self.still_frames = self.still_frames.saturating_add(1);
+        }
+
+        self.low_res = self.enabled
+            && (camera_moved || (self.over_budget && self.still_frames <= self.refine_after));
+        self.low_res
+    }
+
+    /// Tiempo que tardó el cuadro recién renderizado.
+    pub fn end_frame(&mut self, frame_time: Duration) {
+        // Un cuadro reducido sirve para estimar lo que costaría el completo
+        let full_res_time = if self.low_res { frame_time * FULL_RES_COST } else { frame_time };
+        if full_res_time > self.frame_budget {
+            self.over_budget = true;
+        } else if full_res_time < self.frame_budget.mul_f32(RECOVER_FRACTION) {
+            self.over_budget = false;
+        }
+    }
+
+    pub fn is_low_res(&self) -> bool {
+        self.low_res
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    // Cuadros seguidos con la cámara quieta; devuelve cuáles fueron reducidos.
+    fn still_frames(resolution: &mut DynamicResolution, count: usize, frame_time: Duration) -> Vec<bool> {
+        (0..count)
+            .map(|_| {
+                let low_res = resolution.begin_frame(false);
+                resolution.end_frame(frame_time);
+                low_res
+            })
+            .collect()
+    }
+
+    #[test]
+    fn cheap_frames_refine_as_soon_as_the_camera_stops() {
+        let mut resolution = DynamicResolution::default();
+        assert!(resolution.begin_frame(true));
+        resolution.end_frame(ms(5));
+        assert_eq!(still_frames(&mut resolution, 2, ms(10)), vec![false, false]);
+    }
+
+    #[test]
+    fn over_budget_waits_before_refining() {
+        let mut resolution = DynamicResolution::default();
+        // 20 ms reducido son unos 80 ms a resolución completa
+        assert!(resolution.begin_frame(true));
+        resolution.end_frame(ms(20));
+        assert_eq!(still_frames(&mut resolution, 5, ms(20)), vec![true, true, true, false, false]);
+    }
+
+    #[test]
+    fn over_budget_has_hysteresis() {
+        let mut resolution = DynamicResolution::default();
+        assert!(!resolution.begin_frame(false));
+        resolution.end_frame(ms(60));
+
+        // 11 ms reducido son unos 44 ms: bajo el presupuesto, pero dentro del
+        // margen, así que sigue contando como pasado
+        assert!(resolution.begin_frame(true));
+        resolution.end_frame(ms(11));
+        assert_eq!(still_frames(&mut resolution, 4, ms(11)), vec![true, true, true, false]);
+
+        // Con 36 ms estimados ya no lo está
+        assert!(resolution.begin_frame(true));
+        resolution.end_frame(ms(9));
+        assert_eq!(still_frames(&mut resolution, 1, ms(30)), vec![false]);
+    }
+
+    #[test]
+    fn disabled_always_renders_full_resolution() {
+        let mut resolution = DynamicResolution { enabled: false, ..DynamicResolution::default() };
+        assert!(!resolution.begin_frame(true));
+        resolution.end_frame(ms(200));
+        assert!(!resolution.begin_frame(true));
+        assert!(!resolution.is_low_res());
+    }
+}
@@ -0,0 +1,144 @@
+use std::cell::Cell;
+use nalgebra_glm::Vec3;
+
+use crate::fog::Fog;
+use crate::geometry::Aabb;
+use crate::ibl::EnvironmentLight;
+use crate::light::Light;
+use crate::particles::ParticleSystem;
+use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::scene_graph::SceneNode;
+use crate::sky::Sky;
+
+/// Todo lo que necesita `cast_ray`: geometría, luces y entorno.
+pub struct Scene {
+    // Jerarquía editable; `objects` es su versión aplanada en coordenadas de mundo.
+    pub root: SceneNode,
+    pub objects: Vec<Box<dyn RayIntersect>>,
+    // Ruta en la jerarquía de cada elemento de `objects`.
+    pub object_names: Vec<String>,
+    pub lights: Vec<Light>,
+    pub sky: Sky,
+    pub environment: EnvironmentLight,
+    pub fog: Fog,
+    // Las partículas se intersectan como geometría pero no proyectan sombra.
+    pub particles: Vec<ParticleSystem>,
+    // Rayos lanzados desde la última llamada a `take_ray_count`.
+    ray_count: Cell<u64>,
+    // Aumenta cada vez que cambia `objects`.
+    revision: u64,
+}
+
+impl Scene {
+    pub fn new(root: SceneNode, lights: Vec<Light>, sky: Sky) -> Self {
+        let environment = EnvironmentLight::from_sky(&sky);
+        let (object_names, objects) = root.flatten_named().into_iter().unzip();
+        Scene {
+            objects,
+            object_names,
+            root,
+            lights,
+            sky,
+            environment,
+            fog: Fog::default(),
+            particles: Vec::new(),
+            ray_count: Cell::new(0),
+            revision: 0,
+        }
+    }
+
+    /// Vuelve a aplanar la jerarquía tras mover, ocultar o cambiar nodos.
+    pub fn rebuild_objects(&mut self) {
+        (self.object_names, self.objects) = self.root.flatten_named().into_iter().unzip();
+        self.revision += 1;
+    }
+
+    /// Como `rebuild_objects`, pero solo rehace los objetos del subárbol
+    /// `path` y los deja en el mismo lugar de `objects`.
+    pub fn rebuild_node(&mut self, path: &str) {
+        let Some((node_path, subtree)) = self.root.flatten_subtree(path) else {
+            return;
+        };
+        let prefix = format!("{}/", node_path);
+        let in_subtree = |name: &String| *name == node_path || name.starts_with(&prefix);
+        let Some(start) = self.object_names.iter().position(in_subtree) else {
+            // No tenía objetos (estaba oculto) y no se sabe dónde van
+            self.rebuild_objects();
+            return;
+        };
+        let end = start + self.object_names[start..].iter().take_while(|n| in_subtree(n)).count();
+        let (names, objects): (Vec<_>, Vec<_>) = subtree.into_iter().unzip();
+        self.object_names.splice(start..end, names);
+        self.objects.splice(start..end, objects);
+        self.revision += 1;
+    }
+
+    /// Cambia cada vez que se vuelve a aplanar la jerarquía, para saber si
+    /// algo calculado sobre `objects` sigue valiendo.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Anota un rayo lanzado fuera de `intersect` o `is_occluded`.
+    pub fn count_ray(&self) {
+        self.ray_count.set(self.ray_count.get() + 1);
+    }
+
+    /// Devuelve los rayos contados hasta ahora y reinicia el contador.
+    pub fn take_ray_count(&self) -> u64 {
+        self.ray_count.replace(0)
+    }
+
+    /// Intersección más cercana del rayo con la escena.
+    pub fn intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
+        self.closest(ray_origin, ray_direction).map_or_else(Intersect::empty, |(_, i)| i)
+    }
+
+    /// Como `intersect`, junto con el índice del objeto alcanzado; los
+    /// sistemas de partículas van numerados después de `objects`.
+    pub fn closest(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<(usize, Intersect)> {
+        self.count_ray();
+        let mut closest = None;
+        let mut zbuffer = f32::INFINITY;
+
+        let objects = self.objects.iter().map(|o| o.as_ref() as &dyn RayIntersect);
+        let particles = self.particles.iter().map(|p| p as &dyn RayIntersect);
+        for (index, object) in objects.chain(particles).enumerate() {
+            let i = object.ray_intersect(ray_origin, ray_direction);
+            if i.is_intersecting && i.distance < zbuffer {
+                zbuffer = i.distance;
+                closest = Some((index, i));
+            }
+        }
+        closest
+    }
+
+    /// Nombre del objeto con el índice que devuelve `closest`.
+    pub fn object_name(&self, index: usize) -> String {
+        match self.object_names.get(index) {
+            Some(name) => name.clone(),
+            None => format!("particles/{}", index - self.object_names.len()),
+        }
+    }
+
+    /// Caja en mundo del objeto con el índice que devuelve `closest`.
+    pub fn object_bounds(&self, index: usize) -> Option<Aabb> {
+        match self.objects.get(index) {
+            Some(object) => object.bounds(),
+            None => self.particles.get(index - self.objects.len()).and_then(|p| p.bounds()),
+        }
+    }
+
+    /// Indica si algo tapa la luz vista desde `point`.
+    pub fn is_occluded(&self, point: &Vec3, light: &Light) -> bool {
+        self.count_ray();
+        let to_light = light.position - point;
+        let light_distance = to_light.magnitude();
+        let light_dir = to_light / light_distance;
+
+        self.objects.iter().any(|object| {
+            let i = object.ray_intersect(point, &light_dir);
+            i.is_intersecting && i.distance < light_distance
+        })
+    }
+}
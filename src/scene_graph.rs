@@ -0,0 +1,342 @@
+use std::rc::Rc;
+
+use crate::geometry::{self, Aabb, GeometryIssue};
+use crate::material::MaterialOverride;
+use crate::ray_intersect::RayIntersect;
+use crate::transform::{Transform, Transformed};
+
+/// Objetos aplanados con la ruta del nodo de cada uno.
+pub type NamedObjects = Vec<(String, Box<dyn RayIntersect>)>;
+
+/// Nodo con nombre de la jerarquía de la escena. Su transformación es
+/// relativa al padre; ocultar un nodo oculta también a sus hijos y su ajuste
+/// de material se hereda salvo que un hijo lo sobrescriba.
+#[derive(Clone)]
+pub struct SceneNode {
+    pub name: String,
+    pub transform: Transform,
+    // Pose que escribe `Animator` cada cuadro y se aplica tras `transform`;
+    // así `transform` sigue siendo la pose base y se puede editar.
+    pub animation: Transform,
+    pub visible: bool,
+    pub material: Option<MaterialOverride>,
+    pub object: Option<Rc<dyn RayIntersect>>,
+    pub children: Vec<SceneNode>,
+}
+
+impl SceneNode {
+    pub fn new(name: &str) -> Self {
+        SceneNode {
+            name: name.to_string(),
+            transform: Transform::identity(),
+            animation: Transform::identity(),
+            visible: true,
+            material: None,
+            object: None,
+            children: Vec::new(),
+        }
+    }
+
+    /// Hoja con un solo objeto.
+    pub fn leaf(name: &str, object: impl RayIntersect + 'static) -> Self {
+        SceneNode::new(name).with_object(Rc::new(object))
+    }
+
+    pub fn with_object(mut self, object: Rc<dyn RayIntersect>) -> Self {
+        self.object = Some(object);
+        self
+    }
+
+    pub fn with_transform(mut self, transform: Transform) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    pub fn with_child(mut self, child: SceneNode) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    pub fn with_material(mut self, material: MaterialOverride) -> Self {
+        self.material = Some(material);
+        self
+    }
+
+    /// Copia del subárbol con otro nombre; la geometría se comparte.
+    pub fn duplicate(&self, name: &str) -> SceneNode {
+        let mut copy = self.clone();
+        copy.name = name.to_string();
+        copy
+    }
+
+    /// Búsqueda en profundidad por nombre. Acepta rutas como `cabin/door`.
+    pub fn find(&self, path: &str) -> Option<&SceneNode> {
+        match path.split_once('/') {
+            Some((head, rest)) => self.find(head)?.find_child_path(rest),
+            None => {
+                if self.name == path {
+                    return Some(self);
+                }
+                self.children.iter().find_map(|c| c.find(path))
+            }
+        }
+    }
+
+    pub fn find_mut(&mut self, path: &str) -> Option<&mut SceneNode> {
+        match path.split_once('/') {
+            Some((head, rest)) => self.find_mut(head)?.find_child_path_mut(rest),
+            None => {
+                if self.name == path {
+                    return Some(self);
+                }
+                self.children.iter_mut().find_map(|c| c.find_mut(path))
+            }
+        }
+    }
+
+    fn find_child_path(&self, path: &str) -> Option<&SceneNode> {
+        let (head, rest) = match path.split_once('/') {
+            Some((head, rest)) => (head, Some(rest)),
+            None => (path, None),
+        };
+        let child = self.children.iter().find(|c| c.name == head)?;
+        match rest {
+            Some(rest) => child.find_child_path(rest),
+            None => Some(child),
+        }
+    }
+
+    fn find_child_path_mut(&mut self, path: &str) -> Option<&mut SceneNode> {
+        let (head, rest) = match path.split_once('/') {
+            Some((head, rest)) => (head, Some(rest)),
+            None => (path, None),
+        };
+        let child = self.children.iter_mut().find(|c| c.name == head)?;
+        match rest {
+            Some(rest) => child.find_child_path_mut(rest),
+            None => Some(child),
+        }
+    }
+
+    /// Pose base seguida de la animada.
+    pub fn local_transform(&self) -> Transform {
+        self.transform * self.animation
+    }
+
+    /// Cajas en coordenadas de mundo de los objetos visibles, con su ruta.
+    pub fn world_bounds(&self) -> Vec<(String, Aabb)> {
+        let mut bounds = Vec::new();
+        self.collect_bounds("", Transform::identity(), &mut bounds);
+        bounds
+    }
+
+    fn collect_bounds(&self, prefix: &str, parent: Transform, bounds: &mut Vec<(String, Aabb)>) {
+        if !self.visible {
+            return;
+        }
+        let path = self.path(prefix);
+        let world = parent * self.local_transform();
+        if let Some(b) = self.object.as_ref().and_then(|o| o.bounds()) {
+            bounds.push((path.clone(), b.transformed(&world)));
+        }
+        for child in &self.children {
+            child.collect_bounds(&path, world, bounds);
+        }
+    }
+
+    /// Objetos sin volumen o que se solapan entre sí.
+    pub fn validate(&self) -> Vec<GeometryIssue> {
+        geometry::validate(&self.world_bounds())
+    }
+
+    /// Lista plana de objetos visibles en coordenadas de mundo, para `Scene`,
+    /// con la ruta completa del nodo de cada uno (p. ej. "root/cabin/walls").
+    pub fn flatten_named(&self) -> NamedObjects {
+        let mut objects = Vec::new();
+        self.flatten_into("", Transform::identity(), None, &mut objects);
+        objects
+    }
+
+    /// Como `flatten_named`, solo para el subárbol del nodo `path` (con la
+    /// misma búsqueda que `find`). Devuelve también la ruta completa del nodo;
+    /// la lista queda vacía si el nodo o alguno de sus padres está oculto.
+    pub fn flatten_subtree(&self, path: &str) -> Option<(String, NamedObjects)> {
+        let target = self.find(path)?;
+        self.flatten_target(target, "", Transform::identity(), None, true)
+    }
+
+    fn flatten_target(
+        &self,
+        target: &SceneNode,
+        prefix: &str,
+        parent: Transform,
+        parent_material: Option<&MaterialOverride>,
+        ancestors_visible: bool,
+    ) -> Option<(String, NamedObjects)> {
+        if std::ptr::eq(self, target) {
+            let mut objects = Vec::new();
+            if ancestors_visible {
+                self.flatten_into(prefix, parent, parent_material, &mut objects);
+            }
+            return Some((self.path(prefix), objects));
+        }
+        let path = self.path(prefix);
+        let world = parent * self.local_transform();
+        let material = self.inherited_material(parent_material);
+        self.children
+            .iter()
+            .find_map(|c| c.flatten_target(target, &path, world, material.as_ref(), ancestors_visible && self.visible))
+    }
+
+    fn path(&self, prefix: &str) -> String {
+        if prefix.is_empty() { self.name.clone() } else { format!("{}/{}", prefix, self.name) }
+    }
+
+    fn inherited_material(&self, parent_material: Option<&MaterialOverride>) -> Option<MaterialOverride> {
+        match (parent_material, &self.material) {
+            (Some(parent), Some(own)) => Some(parent.merged(own)),
+            (parent, own) => own.clone().or_else(|| parent.cloned()),
+        }
+    }
+
+    fn flatten_into(
+        &self,
+        prefix: &str,
+        parent: Transform,
+        parent_material: Option<&MaterialOverride>,
+        objects: &mut NamedObjects,
+    ) {
+        if !self.visible {
+            return;
+        }
+        let path = self.path(prefix);
+        let world = parent * self.local_transform();
+        let material = self.inherited_material(parent_material);
+        if let Some(object) = &self.object {
+            // Sin transformación ni material no hace falta envolver el objeto
+            if world == Transform::identity() && material.is_none() {
+                objects.push((path.clone(), Box::new(object.clone())));
+            } else {
+                let placed = Transformed::new(object.clone(), world);
+                let placed = match &material {
+                    Some(material) => placed.with_material(material.clone()),
+                    None => placed,
+                };
+                objects.push((path.clone(), Box::new(placed)));
+            }
+        }
+        for child in &self.children {
+            child.flatten_into(&path, world, material.as_ref(), objects);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::Cube;
+    use crate::material::MaterialParam;
+    use crate::texture::Texture;
+    use nalgebra_glm::Vec3;
+
+    fn unit_cube() -> Cube {
+        let texture = Rc::new(Texture::new("textures/snow.png"));
+        Cube::try_new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0), texture.clone(), texture.clone(), texture)
+            .unwrap()
+    }
+
+    // root
+    // ├── cabin
+    // │   ├── door
+    // │   │   └── panel
+    // │   └── roof
+    // └── shed
+    //     └── door
+    fn tree() -> SceneNode {
+        SceneNode::new("root")
+            .with_child(
+                SceneNode::new("cabin")
+                    .with_child(SceneNode::new("door").with_child(SceneNode::leaf("panel", unit_cube())))
+                    .with_child(SceneNode::leaf("roof", unit_cube())),
+            )
+            .with_child(SceneNode::new("shed").with_child(SceneNode::new("door").with_transform(Transform::translation(
+                Vec3::new(5.0, 0.0, 0.0),
+            ))))
+    }
+
+    fn names(objects: &NamedObjects) -> Vec<&str> {
+        objects.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    #[test]
+    fn find_follows_paths() {
+        let root = tree();
+        // Sin ruta gana el primero en profundidad
+        assert_eq!(root.find("door").unwrap().children.len(), 1);
+        assert_eq!(root.find("shed/door").unwrap().transform, Transform::translation(Vec3::new(5.0, 0.0, 0.0)));
+        assert_eq!(root.find("cabin/door/panel").unwrap().name, "panel");
+        assert!(root.find("cabin/panel").is_none());
+        assert!(root.find("shed/roof").is_none());
+    }
+
+    #[test]
+    fn material_overrides_are_inherited_and_merged() {
+        let mut root = tree();
+        let cabin = root.find_mut("cabin").unwrap();
+        cabin.material = Some(
+            MaterialOverride::default().with(MaterialParam::Reflectivity, 0.3).with(MaterialParam::Specular, 0.2),
+        );
+        root.find_mut("cabin/door/panel").unwrap().material = Some(MaterialOverride::default().with(MaterialParam::Specular, 0.7));
+
+        let origin = Vec3::new(0.5, 5.0, 0.5);
+        let down = Vec3::new(0.0, -1.0, 0.0);
+        let objects = root.flatten_named();
+        assert_eq!(names(&objects), vec!["root/cabin/door/panel", "root/cabin/roof"]);
+
+        let panel = objects[0].1.ray_intersect(&origin, &down);
+        assert!(panel.is_intersecting);
+        assert_eq!(panel.material.albedo[2], 0.3);
+        assert_eq!(panel.material.albedo[1], 0.7);
+
+        let roof = objects[1].1.ray_intersect(&origin, &down);
+        assert_eq!(roof.material.albedo[2], 0.3);
+        assert_eq!(roof.material.albedo[1], 0.2);
+    }
+
+    #[test]
+    fn hidden_parents_cull_the_subtree() {
+        let mut root = tree();
+        root.find_mut("cabin/door").unwrap().visible = false;
+
+        let (path, objects) = root.flatten_subtree("cabin/door/panel").unwrap();
+        assert_eq!(path, "root/cabin/door/panel");
+        assert!(objects.is_empty());
+
+        let (path, objects) = root.flatten_subtree("cabin").unwrap();
+        assert_eq!(path, "root/cabin");
+        assert_eq!(names(&objects), vec!["root/cabin/roof"]);
+        assert!(root.flatten_subtree("cabin/window").is_none());
+    }
+
+    #[test]
+    fn duplicate_renames_and_shares_geometry() {
+        let root = tree();
+        let cabin = root.find("cabin").unwrap();
+        let mut copy = cabin.duplicate("cabin2");
+        copy.transform = Transform::translation(Vec3::new(10.0, 0.0, 0.0));
+
+        assert_eq!(copy.name, "cabin2");
+        assert_eq!(copy.find("door").unwrap().name, "door");
+        let original = cabin.find("roof").unwrap().object.as_ref().unwrap();
+        let shared = copy.find("roof").unwrap().object.as_ref().unwrap();
+        assert!(Rc::ptr_eq(original, shared));
+        assert_eq!(cabin.transform, Transform::identity());
+
+        let root = SceneNode::new("root").with_child(cabin.clone()).with_child(copy);
+        let (_, objects) = root.flatten_subtree("cabin2").unwrap();
+        assert_eq!(names(&objects), vec!["root/cabin2/door/panel", "root/cabin2/roof"]);
+        let hit = objects[1].1.ray_intersect(&Vec3::new(10.5, 5.0, 0.5), &Vec3::new(0.0, -1.0, 0.0));
+        assert!(hit.is_intersecting);
+        assert!((hit.point - Vec3::new(10.5, 1.0, 0.5)).norm() < 1e-4);
+    }
+}
@@ -0,0 +1,262 @@
+use std::rc::Rc;
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+
+use crate::color::Color;
+use crate::daycycle::DayCycle;
+use crate::math::smoothstep;
+use crate::skybox::Skybox;
+
+/// Mapa de entorno que empieza a verse en `time` y dura hasta el siguiente.
+pub struct SkyKeyframe {
+    pub time: f32,
+    pub skybox: Rc<Skybox>,
+}
+
+impl SkyKeyframe {
+    pub fn new(time: f32, skybox: Rc<Skybox>) -> Self {
+        SkyKeyframe { time, skybox }
+    }
+}
+
+/// Modelo analítico de cielo (Preetham et al.) con sol, atardecer, estrellas y luna.
+/// Sol y luna los coloca `DayCycle`.
+#[derive(Debug, Clone)]
+pub struct ProceduralSky {
+    pub sun_direction: Vec3,
+    pub moon_direction: Vec3,
+    pub turbidity: f32,
+    pub exposure: f32,
+    pub ground_color: Vec3,
+    pub night_color: Vec3,
+    pub star_density: f32,
+    pub moon_size: f32,
+}
+
+impl Default for ProceduralSky {
+    fn default() -> Self {
+        let cycle = DayCycle::default();
+        ProceduralSky {
+            sun_direction: cycle.sun_direction(),
+            moon_direction: cycle.moon_direction(),
+            turbidity: 3.0,
+            exposure: 0.06,
+            ground_color: Vec3::new(0.35, 0.36, 0.4),
+            night_color: Vec3::new(0.01, 0.015, 0.04),
+            star_density: 0.004,
+            moon_size: 0.03,
+        }
+    }
+}
+
+struct PerezCoefficients([f32; 5]);
+
+impl PerezCoefficients {
+    fn eval(&self, cos_theta: f32, gamma: f32) -> f32 {
+        let [a, b, c, d, e] = self.0;
+        let cos_gamma = gamma.cos();
+        (1.0 + a * (b / cos_theta.max(0.01)).exp()) * (1.0 + c * (d * gamma).exp() + e * cos_gamma * cos_gamma)
+    }
+}
+
+impl ProceduralSky {
+    fn perez(&self) -> [PerezCoefficients; 3] {
+        let t = self.turbidity;
+        [
+            PerezCoefficients([0.1787 * t - 1.4630, -0.3554 * t + 0.4275, -0.0227 * t + 5.3251, 0.1206 * t - 2.5771, -0.0670 * t + 0.3703]),
+            PerezCoefficients([-0.0193 * t - 0.2592, -0.0665 * t + 0.0008, -0.0004 * t + 0.2125, -0.0641 * t - 0.8989, -0.0033 * t + 0.0452]),
+            PerezCoefficients([-0.0167 * t - 0.2608, -0.0950 * t + 0.0092, -0.0079 * t + 0.2102, -0.0441 * t - 1.6537, -0.0109 * t + 0.0529]),
+        ]
+    }
+
+    fn zenith(&self, theta_s: f32) -> (f32, f32, f32) {
+        let t = self.turbidity;
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_s);
+        let luminance = ((4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192).max(0.0);
+
+        let th = [theta_s * theta_s * theta_s, theta_s * theta_s, theta_s, 1.0];
+        let dot = |k: [f32; 4]| k[0] * th[0] + k[1] * th[1] + k[2] * th[2] + k[3] * th[3];
+        let x = t * t * dot([0.00166, -0.00375, 0.00209, 0.0])
+            + t * dot([-0.02903, 0.06377, -0.03202, 0.00394])
+            + dot([0.11693, -0.21196, 0.06052, 0.25886]);
+        let y = t * t * dot([0.00275, -0.00610, 0.00317, 0.0])
+            + t * dot([-0.04214, 0.08970, -0.04153, 0.00516])
+            + dot([0.15346, -0.26756, 0.06670, 0.26688]);
+        (luminance, x, y)
+    }
+
+    // Cielo diurno en RGB lineal, con el sol clavado como mucho en el horizonte.
+    fn daylight(&self, direction: &Vec3, sun: &Vec3) -> Vec3 {
+        let theta_s = sun.y.clamp(0.0, 1.0).acos();
+        let cos_theta = direction.y.max(0.0);
+        let gamma = direction.dot(sun).clamp(-1.0, 1.0).acos();
+
+        let [perez_y, perez_x, perez_yc] = self.perez();
+        let (zenith_y, zenith_x, zenith_yc) = self.zenith(theta_s);
+
+        let ratio = |p: &PerezCoefficients| p.eval(cos_theta, gamma) / p.eval(1.0, theta_s);
+        let luminance = zenith_y * ratio(&perez_y);
+        let x = zenith_x * ratio(&perez_x);
+        let y = (zenith_yc * ratio(&perez_yc)).max(1e-4);
+
+        // xyY -> XYZ -> sRGB lineal
+        let big_x = x / y * luminance;
+        let big_z = (1.0 - x - y) / y * luminance;
+        Vec3::new(
+            3.2406 * big_x - 1.5372 * luminance - 0.4986 * big_z,
+            -0.9689 * big_x + 1.8758 * luminance + 0.0415 * big_z,
+            0.0557 * big_x - 0.2040 * luminance + 1.0570 * big_z,
+        )
+        .map(|c| c.max(0.0))
+            * self.exposure
+    }
+
+    fn stars(&self, direction: &Vec3) -> f32 {
+        let cell = direction * 300.0;
+        let h = (cell.x.floor() as i32 as u32).wrapping_mul(0x8da6_b343)
+            ^ (cell.y.floor() as i32 as u32).wrapping_mul(0xd816_3841)
+            ^ (cell.z.floor() as i32 as u32).wrapping_mul(0xcb1a_b31f);
+        let h = (h ^ (h >> 13)).wrapping_mul(0x5bd1_e995);
+        let value = (h & 0xffff) as f32 / 65535.0;
+        if value < self.star_density {
+            0.4 + value / self.star_density * 0.6
+        } else {
+            0.0
+        }
+    }
+
+    /// Color de pantalla (sRGB en [0, 1]) visto en la dirección dada.
+    pub fn sample(&self, direction: &Vec3) -> Vec3 {
+        let direction = direction.normalize();
+        let sun = self.sun_direction.normalize();
+        let up = direction.y;
+
+        let day = smoothstep(-0.12, 0.05, sun.y);
+        let sunset = smoothstep(0.35, 0.0, sun.y.abs()) * smoothstep(-0.2, 0.0, sun.y);
+
+        let mut color = Vec3::zeros();
+
+        if day > 0.0 {
+            let mut sky = self.daylight(&Vec3::new(direction.x, up.max(0.0), direction.z).normalize(), &sun);
+            // Tonos cálidos cerca del horizonte y en el lado del sol al atardecer
+            let horizon = (-up.max(0.0) * 6.0).exp();
+            let facing_sun = direction.dot(&sun).max(0.0).powf(4.0);
+            let glow = Vec3::new(1.0, 0.45, 0.15) * sunset * horizon * (0.4 + 0.6 * facing_sun);
+            sky += glow;
+            color += sky * day;
+        }
+
+        let night = 1.0 - day;
+        if night > 0.0 {
+            let mut sky = self.night_color;
+            if up > 0.0 {
+                sky += Vec3::new(1.0, 1.0, 1.0) * self.stars(&direction) * up.sqrt();
+            }
+            let moon = self.moon_direction.normalize();
+            if direction.dot(&moon) > (1.0 - self.moon_size * self.moon_size) {
+                sky = Vec3::new(0.85, 0.87, 0.9);
+            }
+            color += sky * night;
+        }
+
+        if up < 0.0 {
+            let ground = self.ground_color * (0.05 + 0.95 * day);
+            color = color.lerp(&ground, smoothstep(0.0, -0.1, up));
+        } else if direction.dot(&sun) > 0.9995 && sun.y > -0.05 {
+            color += Vec3::new(1.0, 0.9, 0.7) * 3.0;
+        }
+
+        color.map(|c| (1.0 - (-c).exp()).powf(1.0 / 2.2))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkyMode {
+    Maps,
+    Procedural,
+}
+
+/// Cielo de la escena: mezcla mapas de entorno a lo largo del día o usa el
+/// modelo procedural, según `mode`.
+pub struct Sky {
+    pub mode: SkyMode,
+    pub keyframes: Vec<SkyKeyframe>,
+    pub blend_duration: f32,
+    pub procedural: ProceduralSky,
+    time_of_day: f32,
+}
+
+impl Sky {
+    pub fn with_maps(mut keyframes: Vec<SkyKeyframe>, blend_duration: f32) -> Self {
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Sky {
+            mode: SkyMode::Maps,
+            keyframes,
+            blend_duration,
+            procedural: ProceduralSky::default(),
+            time_of_day: 0.0,
+        }
+    }
+
+    /// Toma la hora y la posición del sol y la luna del ciclo de día, para
+    /// que el cielo coincida con las luces de la escena.
+    pub fn update(&mut self, day_cycle: &DayCycle) {
+        self.time_of_day = day_cycle.time_of_day;
+        self.procedural.sun_direction = day_cycle.sun_direction();
+        self.procedural.moon_direction = day_cycle.moon_direction();
+    }
+
+    pub fn toggle_mode(&mut self) {
+        self.mode = match self.mode {
+            SkyMode::Maps => SkyMode::Procedural,
+            SkyMode::Procedural if !self.keyframes.is_empty() => SkyMode::Maps,
+            SkyMode::Procedural => SkyMode::Procedural,
+        };
+    }
+
+    // Mapa actual, siguiente y peso del siguiente; la transición ocupa los
+    // últimos `blend_duration` antes de que empiece el siguiente mapa.
+    fn map_weights(&self) -> Option<(usize, usize, f32)> {
+        let count = self.keyframes.len();
+        if count == 0 {
+            return None;
+        }
+        if count == 1 {
+            return Some((0, 0, 0.0));
+        }
+
+        let t = self.time_of_day;
+        let current = self
+            .keyframes
+            .iter()
+            .rposition(|k| k.time <= t)
+            .unwrap_or(count - 1);
+        let next = (current + 1) % count;
+
+        let until_next = (self.keyframes[next].time - t).rem_euclid(1.0);
+        let weight = if self.blend_duration > 0.0 && until_next < self.blend_duration {
+            1.0 - until_next / self.blend_duration
+        } else {
+            0.0
+        };
+        Some((current, next, weight))
+    }
+
+    pub fn sample(&self, direction: &Vec3) -> Vec3 {
+        match (self.mode, self.map_weights()) {
+            (SkyMode::Maps, Some((current, next, weight))) => {
+                let a = self.keyframes[current].skybox.get_color(direction).to_vec3();
+                if weight <= 0.0 {
+                    return a;
+                }
+                let b = self.keyframes[next].skybox.get_color(direction).to_vec3();
+                a.lerp(&b, weight)
+            }
+            _ => self.procedural.sample(direction),
+        }
+    }
+
+    pub fn get_color(&self, direction: &Vec3) -> Color {
+        Color::from_vec3(&self.sample(direction))
+    }
+}
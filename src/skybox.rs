@@ -0,0 +1,385 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use image::{ImageError, Rgb32FImage};
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+
+use crate::color::Color;
+
+#[derive(Debug)]
+pub enum SkyboxError {
+    Io { path: PathBuf, source: std::io::Error },
+    Image { path: PathBuf, source: ImageError },
+    MissingFace { directory: PathBuf, face: Face },
+    NotSquare { face: Face, width: u32, height: u32 },
+    FaceSizeMismatch { face: Face, expected: u32, found: (u32, u32) },
+    InvalidCrossLayout { width: u32, height: u32 },
+}
+
+impl fmt::Display for SkyboxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SkyboxError::Io { path, source } => {
+                write!(f, "failed to read skybox directory {}: {}", path.display(), source)
+            }
+            SkyboxError::Image { path, source } => {
+                write!(f, "failed to load skybox image {}: {}", path.display(), source)
+            }
+            SkyboxError::MissingFace { directory, face } => {
+                write!(f, "skybox directory {} has no {} face", directory.display(), face.name())
+            }
+            SkyboxError::NotSquare { face, width, height } => {
+                write!(f, "skybox {} face is not square ({}x{})", face.name(), width, height)
+            }
+            SkyboxError::FaceSizeMismatch { face, expected, found } => write!(
+                f,
+                "skybox {} face is {}x{}, expected {}x{}",
+                face.name(),
+                found.0,
+                found.1,
+                expected,
+                expected
+            ),
+            SkyboxError::InvalidCrossLayout { width, height } => write!(
+                f,
+                "{}x{} is not a 4x3 or 3x4 cross layout",
+                width, height
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SkyboxError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SkyboxError::Io { source, .. } => Some(source),
+            SkyboxError::Image { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Face {
+    Right,
+    Left,
+    Top,
+    Bottom,
+    Front,
+    Back,
+}
+
+impl Face {
+    pub const ALL: [Face; 6] = [Face::Right, Face::Left, Face::Top, Face::Bottom, Face::Front, Face::Back];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Face::Right => "right",
+            Face::Left => "left",
+            Face::Top => "top",
+            Face::Bottom => "bottom",
+            Face::Front => "front",
+            Face::Back => "back",
+        }
+    }
+
+    // Nombres alternativos habituales (+x, -x, ...).
+    fn short_name(self) -> &'static str {
+        match self {
+            Face::Right => "px",
+            Face::Left => "nx",
+            Face::Top => "py",
+            Face::Bottom => "ny",
+            Face::Front => "pz",
+            Face::Back => "nz",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+enum Source {
+    // Caras en el orden de `Face::ALL`.
+    Cube(Vec<Rgb32FImage>),
+    Equirect(Rgb32FImage),
+}
+
+pub struct Skybox {
+    source: Source,
+    hdr: bool,
+    pub exposure: f32,
+}
+
+fn open_rgb32f(path: &Path) -> Result<Rgb32FImage, SkyboxError> {
+    image::open(path)
+        .map(|img| img.to_rgb32f())
+        .map_err(|source| SkyboxError::Image { path: path.to_path_buf(), source })
+}
+
+fn is_hdr_path(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase()).as_deref(),
+        Some("hdr") | Some("exr")
+    )
+}
+
+fn validate_faces(faces: &[Rgb32FImage]) -> Result<(), SkyboxError> {
+    let size = faces[0].width();
+    for (face, image) in Face::ALL.iter().zip(faces) {
+        let (width, height) = image.dimensions();
+        if width != height {
+            return Err(SkyboxError::NotSquare { face: *face, width, height });
+        }
+        if width != size {
+            return Err(SkyboxError::FaceSizeMismatch { face: *face, expected: size, found: (width, height) });
+        }
+    }
+    Ok(())
+}
+
+// Muestreo bilineal con las coordenadas recortadas al borde de la imagen.
+fn sample_clamped(image: &Rgb32FImage, u: f32, v: f32) -> Vec3 {
+    let (width, height) = image.dimensions();
+    let x = (u * width as f32 - 0.5).clamp(0.0, (width - 1) as f32);
+    let y = (v * height as f32 - 0.5).clamp(0.0, (height - 1) as f32);
+    let x0 = x.floor() as u32;
+    let y0 = y.floor() as u32;
+    let x1 = (x0 + 1).min(width - 1);
+    let y1 = (y0 + 1).min(height - 1);
+    bilinear(image, x0, y0, x1, y1, x.fract(), y.fract())
+}
+
+// Igual que `sample_clamped` pero repitiendo horizontalmente (mapas equirectangulares).
+fn sample_wrapped_x(image: &Rgb32FImage, u: f32, v: f32) -> Vec3 {
+    let (width, height) = image.dimensions();
+    let x = (u * width as f32 - 0.5).rem_euclid(width as f32);
+    let y = (v * height as f32 - 0.5).clamp(0.0, (height - 1) as f32);
+    let x0 = (x.floor() as u32).min(width - 1);
+    let y0 = y.floor() as u32;
+    let x1 = (x0 + 1) % width;
+    let y1 = (y0 + 1).min(height - 1);
+    bilinear(image, x0, y0, x1, y1, x.fract(), y.fract())
+}
+
+fn bilinear(image: &Rgb32FImage, x0: u32, y0: u32, x1: u32, y1: u32, tx: f32, ty: f32) -> Vec3 {
+    let texel = |x: u32, y: u32| {
+        let p = image.get_pixel(x, y);
+        Vec3::new(p[0], p[1], p[2])
+    };
+    let top = texel(x0, y0).lerp(&texel(x1, y0), tx);
+    let bottom = texel(x0, y1).lerp(&texel(x1, y1), tx);
+    top.lerp(&bottom, ty)
+}
+
+// Recorta las seis caras de una cruz horizontal (4x3) o vertical (3x4), en
+// el orden de `Face::ALL`.
+fn split_cross(image: &Rgb32FImage) -> Result<Vec<Rgb32FImage>, SkyboxError> {
+    let (width, height) = image.dimensions();
+
+    // Posición (columna, fila) de cada cara en la cruz, en el orden de `Face::ALL`.
+    let (size, cells, vertical) = if width * 3 == height * 4 {
+        (width / 4, [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (3, 1)], false)
+    } else if width * 4 == height * 3 {
+        (width / 3, [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (1, 3)], true)
+    } else {
+        return Err(SkyboxError::InvalidCrossLayout { width, height });
+    };
+    if size == 0 {
+        return Err(SkyboxError::InvalidCrossLayout { width, height });
+    }
+
+    let faces = Face::ALL
+        .iter()
+        .zip(cells)
+        .map(|(face, (col, row))| {
+            let face_image = image::imageops::crop_imm(image, col * size, row * size, size, size).to_image();
+            // En la cruz vertical la cara trasera está girada 180 grados.
+            if vertical && *face == Face::Back {
+                image::imageops::rotate180(&face_image)
+            } else {
+                face_image
+            }
+        })
+        .collect();
+    Ok(faces)
+}
+
+impl Skybox {
+    fn from_faces(faces: Vec<Rgb32FImage>, hdr: bool) -> Result<Self, SkyboxError> {
+        validate_faces(&faces)?;
+        Ok(Skybox { source: Source::Cube(faces), hdr, exposure: 1.0 })
+    }
+
+    /// Carga seis imágenes de un directorio: `right`, `left`, `top`, `bottom`,
+    /// `front`, `back` (o `px`, `nx`, `py`, `ny`, `pz`, `nz`) con cualquier extensión.
+    pub fn from_directory(directory: impl AsRef<Path>) -> Result<Self, SkyboxError> {
+        let directory = directory.as_ref();
+        let entries: Vec<PathBuf> = std::fs::read_dir(directory)
+            .map_err(|source| SkyboxError::Io { path: directory.to_path_buf(), source })?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .collect();
+
+        let mut faces = Vec::with_capacity(6);
+        let mut hdr = false;
+        for face in Face::ALL {
+            let path = entries
+                .iter()
+                .find(|path| {
+                    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+                    stem.eq_ignore_ascii_case(face.name()) || stem.eq_ignore_ascii_case(face.short_name())
+                })
+                .ok_or_else(|| SkyboxError::MissingFace { directory: directory.to_path_buf(), face })?;
+            hdr |= is_hdr_path(path);
+            faces.push(open_rgb32f(path)?);
+        }
+        Self::from_faces(faces, hdr)
+    }
+
+    /// Carga una imagen en cruz horizontal (4x3) o vertical (3x4).
+    pub fn from_cross(path: impl AsRef<Path>) -> Result<Self, SkyboxError> {
+        let path = path.as_ref();
+        let faces = split_cross(&open_rgb32f(path)?)?;
+        Self::from_faces(faces, is_hdr_path(path))
+    }
+
+    /// Carga un mapa de entorno equirectangular (`.hdr`, `.exr` o LDR).
+    pub fn from_equirect(path: impl AsRef<Path>) -> Result<Self, SkyboxError> {
+        let path = path.as_ref();
+        let image = open_rgb32f(path)?;
+        Ok(Skybox { source: Source::Equirect(image), hdr: is_hdr_path(path), exposure: 1.0 })
+    }
+
+    /// Elige el cargador según la ruta: directorio, mapa HDR o cruz.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SkyboxError> {
+        let path = path.as_ref();
+        if path.is_dir() {
+            Self::from_directory(path)
+        } else if is_hdr_path(path) {
+            Self::from_equirect(path)
+        } else {
+            Self::from_cross(path)
+        }
+    }
+
+    /// Radiancia en la dirección dada, sin mapeo de tonos.
+    pub fn sample(&self, direction: &Vec3) -> Vec3 {
+        let radiance = match &self.source {
+            Source::Cube(faces) => {
+                let (u, v, face) = cube_face_uv(direction);
+                sample_clamped(&faces[face.index()], u, 1.0 - v)
+            }
+            Source::Equirect(image) => {
+                let d = direction.normalize();
+                let u = 0.5 + d.z.atan2(d.x) / (2.0 * PI);
+                let v = d.y.clamp(-1.0, 1.0).acos() / PI;
+                sample_wrapped_x(image, u, v)
+            }
+        };
+        radiance * self.exposure
+    }
+
+    pub fn get_color(&self, direction: &Vec3) -> Color {
+        let radiance = self.sample(direction);
+        let display = if self.hdr {
+            // Reinhard + gamma para llevar el HDR al rango de pantalla.
+            radiance.map(|c| (c / (1.0 + c)).powf(1.0 / 2.2))
+        } else {
+            radiance
+        };
+        Color::from_vec3(&display)
+    }
+}
+
+fn cube_face_uv(direction: &Vec3) -> (f32, f32, Face) {
+    let abs_x = direction.x.abs();
+    let abs_y = direction.y.abs();
+    let abs_z = direction.z.abs();
+
+    if abs_x >= abs_y && abs_x >= abs_z {
+        if direction.x > 0.0 {
+            ((direction.z / abs_x + 1.0) / 2.0, (direction.y / abs_x + 1.0) / 2.0, Face::Right)
+        } else {
+            ((-direction.z / abs_x + 1.0) / 2.0, (direction.y / abs_x + 1.0) / 2.0, Face::Left)
+        }
+    } else if abs_y >= abs_x && abs_y >= abs_z {
+        if direction.y > 0.0 {
+            ((direction.x / abs_y + 1.0) / 2.0, (-direction.z / abs_y + 1.0) / 2.0, Face::Top)
+        } else {
+            ((direction.x / abs_y + 1.0) / 2.0, (direction.z / abs_y + 1.0) / 2.0, Face::Bottom)
+        }
+    } else if direction.z > 0.0 {
+        ((-direction.x / abs_z + 1.0) / 2.0, (direction.y / abs_z + 1.0) / 2.0, Face::Front)
+    } else {
+        ((direction.x / abs_z + 1.0) / 2.0, (direction.y / abs_z + 1.0) / 2.0, Face::Back)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    fn solid(width: u32, height: u32, value: f32) -> Rgb32FImage {
+        Rgb32FImage::from_pixel(width, height, Rgb([value; 3]))
+    }
+
+    #[test]
+    fn faces_must_be_square_and_equal() {
+        let mut faces: Vec<_> = (0..6).map(|_| solid(4, 4, 0.5)).collect();
+        assert!(validate_faces(&faces).is_ok());
+
+        faces[2] = solid(4, 3, 0.5);
+        assert!(matches!(
+            validate_faces(&faces),
+            Err(SkyboxError::NotSquare { face: Face::Top, width: 4, height: 3 })
+        ));
+
+        faces[2] = solid(4, 4, 0.5);
+        faces[5] = solid(8, 8, 0.5);
+        assert!(matches!(
+            validate_faces(&faces),
+            Err(SkyboxError::FaceSizeMismatch { face: Face::Back, expected: 4, found: (8, 8) })
+        ));
+    }
+
+    // Cruz con cada celda rellena con un valor distinto según su posición.
+    fn cross(columns: u32, rows: u32, size: u32) -> Rgb32FImage {
+        Rgb32FImage::from_fn(columns * size, rows * size, |x, y| {
+            let (col, row) = (x / size, y / size);
+            Rgb([(row * columns + col) as f32, (x % size) as f32, (y % size) as f32])
+        })
+    }
+
+    #[test]
+    fn horizontal_cross_is_split_by_cell() {
+        let faces = split_cross(&cross(4, 3, 2)).unwrap();
+        let cells: Vec<f32> = faces.iter().map(|f| f.get_pixel(0, 0)[0]).collect();
+        // right (2,1), left (0,1), top (1,0), bottom (1,2), front (1,1), back (3,1)
+        assert_eq!(cells, vec![6.0, 4.0, 1.0, 9.0, 5.0, 7.0]);
+        assert!(faces.iter().all(|f| f.dimensions() == (2, 2)));
+        assert_eq!(faces[0].get_pixel(1, 0).0, [6.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn vertical_cross_rotates_the_back_face() {
+        let faces = split_cross(&cross(3, 4, 2)).unwrap();
+        assert_eq!(faces[Face::Back.index()].get_pixel(0, 0).0, [10.0, 1.0, 1.0]);
+        assert_eq!(faces[Face::Front.index()].get_pixel(0, 0).0, [4.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn other_layouts_are_rejected() {
+        assert!(matches!(
+            split_cross(&solid(6, 6, 0.0)),
+            Err(SkyboxError::InvalidCrossLayout { width: 6, height: 6 })
+        ));
+    }
+
+    #[test]
+    fn missing_directory_reports_the_io_error() {
+        let missing = std::env::temp_dir().join("proyecto2-missing-skybox");
+        assert!(matches!(Skybox::from_directory(&missing), Err(SkyboxError::Io { path, .. }) if path == missing));
+    }
+}
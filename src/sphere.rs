@@ -0,0 +1,91 @@
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+use crate::ray_intersect::{RayIntersect, Intersect, Boundary, Span};
+use crate::material::Material;
+use crate::geometry::Aabb;
+
+#[derive(Clone)]
+pub struct Sphere {
+    pub center: Vec3,
+    pub radius: f32,
+    pub material: Material,
+}
+
+impl Sphere {
+    pub fn new(center: Vec3, radius: f32, material: Material) -> Self {
+        Sphere {
+            center,
+            radius,
+            material,
+        }
+    }
+
+    // Distancias de entrada y salida del rayo (pueden ser negativas).
+    pub fn hit_distances(center: &Vec3, radius: f32, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<(f32, f32)> {
+        let oc = ray_origin - center;
+        let a = ray_direction.dot(ray_direction);
+        let b = oc.dot(ray_direction);
+        let c = oc.dot(&oc) - radius * radius;
+        let discriminant = b * b - a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        let root = discriminant.sqrt();
+        Some(((-b - root) / a, (-b + root) / a))
+    }
+
+    fn boundary(&self, ray_origin: &Vec3, ray_direction: &Vec3, distance: f32) -> Boundary {
+        let point = ray_origin + ray_direction * distance;
+        Boundary { distance, normal: (point - self.center).normalize() }
+    }
+
+    fn hit_at(&self, ray_origin: &Vec3, ray_direction: &Vec3, distance: f32) -> Intersect {
+        let point = ray_origin + ray_direction * distance;
+        let normal = (point - self.center).normalize();
+        let (u, v) = self.get_uv(&normal);
+
+        let mut intersect = Intersect::new(point, normal, distance, self.material.clone(), Some(u), Some(v));
+        intersect.local_point = point - self.center;
+        intersect
+    }
+
+    fn get_uv(&self, normal: &Vec3) -> (f32, f32) {
+        let u = 0.5 + normal.z.atan2(normal.x) / (2.0 * PI);
+        let v = 0.5 - normal.y.asin() / PI;
+        (u, v)
+    }
+}
+
+impl RayIntersect for Sphere {
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
+        let (t0, t1) = match Sphere::hit_distances(&self.center, self.radius, ray_origin, ray_direction) {
+            Some(hit) => hit,
+            None => return Intersect::empty(),
+        };
+        let distance = if t0 >= 0.0 { t0 } else { t1 };
+        if distance < 0.0 {
+            return Intersect::empty();
+        }
+        self.hit_at(ray_origin, ray_direction, distance)
+    }
+
+    fn spans(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<Vec<Span>> {
+        let spans = match Sphere::hit_distances(&self.center, self.radius, ray_origin, ray_direction) {
+            Some((t0, t1)) => vec![Span {
+                enter: self.boundary(ray_origin, ray_direction, t0),
+                exit: self.boundary(ray_origin, ray_direction, t1),
+            }],
+            None => Vec::new(),
+        };
+        Some(spans)
+    }
+
+    fn surface_at(&self, ray_origin: &Vec3, ray_direction: &Vec3, distance: f32) -> Option<Intersect> {
+        Some(self.hit_at(ray_origin, ray_direction, distance))
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        let extent = Vec3::repeat(self.radius);
+        Some(Aabb { min: self.center - extent, max: self.center + extent })
+    }
+}
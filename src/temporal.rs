@@ -0,0 +1,219 @@
+use nalgebra_glm::Vec3;
+
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::denoise::Guides;
+use crate::framebuffer::Framebuffer;
+
+// Cuadro anterior ya acumulado, con la cámara que lo vio.
+#[derive(Debug, Clone)]
+struct History {
+    width: usize,
+    height: usize,
+    camera: Camera,
+    color: Vec<Vec3>,
+    depth: Vec<f32>,
+    normal: Vec<Vec3>,
+    // Cuadros promediados en cada píxel.
+    samples: Vec<f32>,
+}
+
+/// Mezcla cada cuadro con los anteriores: cada punto visible se reproyecta
+/// con la cámara del cuadro anterior para encontrar su historia, que se
+/// descarta si allí se veía otra superficie (desoclusión o un objeto que se
+/// movió). Antes de mezclar, la historia se limita a los colores que hay
+/// alrededor del píxel en el cuadro actual, para que un cambio de luz que no
+/// se avisó con `reset` no deje estelas. Con la vista quieta los rayos se
+/// desplazan dentro del píxel, así que la imagen converge también en los bordes.
+#[derive(Debug, Clone)]
+pub struct TemporalAccumulator {
+    pub enabled: bool,
+    // Cuadros que se promedian como mucho: más da una imagen más estable,
+    // pero tarda más en seguir los cambios de luz.
+    pub max_samples: f32,
+    // Diferencia de distancia, relativa a la esperada, que aún es la misma
+    // superficie.
+    pub depth_tolerance: f32,
+    // Coseno mínimo entre la normal actual y la de la historia.
+    pub normal_threshold: f32,
+    frame: u32,
+    history: Option<History>,
+}
+
+impl Default for TemporalAccumulator {
+    fn default() -> Self {
+        TemporalAccumulator {
+            enabled: true,
+            max_samples: 16.0,
+            depth_tolerance: 0.05,
+            normal_threshold: 0.9,
+            frame: 0,
+            history: None,
+        }
+    }
+}
+
+// Secuencia de Halton en [0, 1), bien repartida para pocos cuadros.
+fn halton(mut index: u32, base: u32) -> f32 {
+    let mut result = 0.0;
+    let mut fraction = 1.0;
+    while index > 0 {
+        fraction /= base as f32;
+        result += fraction * (index % base) as f32;
+        index /= base;
+    }
+    result
+}
+
+// Mínimo y máximo por canal de los 3x3 píxeles alrededor de (x, y).
+fn neighbourhood(colors: &[Vec3], width: usize, height: usize, x: usize, y: usize) -> (Vec3, Vec3) {
+    let mut lo = colors[y * width + x];
+    let mut hi = lo;
+    for sy in y.saturating_sub(1)..(y + 2).min(height) {
+        for sx in x.saturating_sub(1)..(x + 2).min(width) {
+            let c = colors[sy * width + sx];
+            lo = lo.inf(&c);
+            hi = hi.sup(&c);
+        }
+    }
+    (lo, hi)
+}
+
+impl History {
+    // Color y número de cuadros de la historia en `point`, interpolando los
+    // cuatro píxeles vecinos que ven la misma superficie.
+    fn sample(&self, point: &Vec3, normal: &Vec3, depth_tolerance: f32, normal_threshold: f32) -> Option<(Vec3, f32)> {
+        let (x, y) = self.camera.project(point, self.width as f32, self.height as f32)?;
+        let (x, y) = (x - self.camera.jitter.0, y - self.camera.jitter.1);
+        let expected = (point - self.camera.eye).magnitude();
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+
+        let mut color = Vec3::zeros();
+        let mut samples = 0.0;
+        let mut weight_sum = 0.0;
+        for (dx, dy, weight) in [
+            (0, 0, (1.0 - fx) * (1.0 - fy)),
+            (1, 0, fx * (1.0 - fy)),
+            (0, 1, (1.0 - fx) * fy),
+            (1, 1, fx * fy),
+        ] {
+            let (sx, sy) = (x0 as isize + dx, y0 as isize + dy);
+            if weight <= 0.0 || sx < 0 || sy < 0 || sx as usize >= self.width || sy as usize >= self.height {
+                continue;
+            }
+            let index = sy as usize * self.width + sx as usize;
+            let same_surface = (self.depth[index] - expected).abs() <= depth_tolerance * expected
+                && self.normal[index].dot(normal) >= normal_threshold;
+            if same_surface {
+                color += self.color[index] * weight;
+                samples += self.samples[index] * weight;
+                weight_sum += weight;
+            }
+        }
+        // Con muy poco peso la interpolación amplificaría un solo vecino
+        (weight_sum > 0.01).then(|| (color / weight_sum, samples / weight_sum))
+    }
+}
+
+impl TemporalAccumulator {
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+        self.reset();
+    }
+
+    /// Olvida la historia, p. ej. al cambiar a un modo de depuración o al
+    /// cambiar la luz de la escena.
+    pub fn reset(&mut self) {
+        self.history = None;
+    }
+
+    /// Desplazamiento para los rayos del próximo cuadro, en píxeles dentro de
+    /// [-0.5, 0.5); cero si está desactivado.
+    pub fn next_jitter(&mut self) -> (f32, f32) {
+        if !self.enabled {
+            return (0.0, 0.0);
+        }
+        self.frame = self.frame % 8 + 1;
+        (halton(self.frame, 2) - 0.5, halton(self.frame, 3) - 0.5)
+    }
+
+    /// Mezcla el cuadro recién renderizado con la historia y lo guarda como
+    /// nueva historia. `guides` y `camera` deben ser los usados en `render`.
+    pub fn accumulate(&mut self, framebuffer: &mut Framebuffer, guides: &Guides, camera: &Camera) {
+        let (width, height) = (framebuffer.width, framebuffer.height);
+        if !self.enabled || guides.width != width || guides.height != height {
+            return;
+        }
+
+        let current_frame: Vec<Vec3> = framebuffer.buffer.iter().map(|c| Color::from_hex(*c).to_vec3()).collect();
+        let mut color = Vec::with_capacity(width * height);
+        let mut samples = Vec::with_capacity(width * height);
+        for (index, pixel) in framebuffer.buffer.iter_mut().enumerate() {
+            let current = current_frame[index];
+            // El cielo no tiene punto que reproyectar; se usa tal cual
+            let previous = match &self.history {
+                Some(history) if guides.depth[index].is_finite() => history.sample(
+                    &guides.point[index],
+                    &guides.normal[index],
+                    self.depth_tolerance,
+                    self.normal_threshold,
+                ),
+                _ => None,
+            };
+            match previous {
+                Some((previous_color, previous_samples)) => {
+                    let (lo, hi) = neighbourhood(&current_frame, width, height, index % width, index / width);
+                    let previous_color = previous_color.sup(&lo).inf(&hi);
+                    let n = (previous_samples + 1.0).min(self.max_samples);
+                    let blended = previous_color + (current - previous_color) / n;
+                    *pixel = Color::from_vec3(&blended).to_hex();
+                    color.push(blended);
+                    samples.push(n);
+                }
+                None => {
+                    color.push(current);
+                    samples.push(1.0);
+                }
+            }
+        }
+
+        self.history = Some(History {
+            width,
+            height,
+            camera: camera.clone(),
+            color,
+            depth: guides.depth.clone(),
+            normal: guides.normal.clone(),
+            samples,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn halton_known_values() {
+        assert_eq!(halton(0, 2), 0.0);
+        assert_eq!(halton(1, 2), 0.5);
+        assert_eq!(halton(2, 2), 0.25);
+        assert_eq!(halton(3, 2), 0.75);
+        assert!((halton(1, 3) - 1.0 / 3.0).abs() < 1e-6);
+        assert!((halton(2, 3) - 2.0 / 3.0).abs() < 1e-6);
+        assert!((halton(4, 3) - 4.0 / 9.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn jitter_cycles_inside_the_pixel() {
+        let mut temporal = TemporalAccumulator::default();
+        let first: Vec<(f32, f32)> = (0..8).map(|_| temporal.next_jitter()).collect();
+        let second: Vec<(f32, f32)> = (0..8).map(|_| temporal.next_jitter()).collect();
+        assert_eq!(first, second);
+        assert!(first.iter().all(|(x, y)| (-0.5..0.5).contains(x) && (-0.5..0.5).contains(y)));
+
+        temporal.enabled = false;
+        assert_eq!(temporal.next_jitter(), (0.0, 0.0));
+    }
+}
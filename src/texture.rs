@@ -3,13 +3,19 @@ use image::RgbaImage;
 #[derive(Debug)]
 pub struct Texture {
     image: RgbaImage,
+    path: String,
 }
 
 impl Texture {
     pub fn new(path: &str) -> Self {
         let img = image::open(path).expect("Failed to load texture").to_rgba8();
         let flipped_img = image::imageops::flip_vertical(&img);
-        Texture { image: flipped_img }
+        Texture { image: flipped_img, path: path.to_string() }
+    }
+
+    /// Archivo del que se cargó.
+    pub fn path(&self) -> &str {
+        &self.path
     }
 
     pub fn get_color(&self, u: f32, v: f32) -> [u8; 3] {
@@ -18,4 +24,9 @@ impl Texture {
         let pixel = self.image.get_pixel(x, y);
         [pixel[0], pixel[1], pixel[2]]
     }
+
+    // Repite la textura fuera de [0, 1] en lugar de recortar al borde.
+    pub fn get_color_wrapped(&self, u: f32, v: f32) -> [u8; 3] {
+        self.get_color(u.rem_euclid(1.0), v.rem_euclid(1.0))
+    }
 }
\ No newline at end of file
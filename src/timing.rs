@@ -0,0 +1,95 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Acumula tiempo real y lo reparte en pasos fijos de simulación, para que
+/// la velocidad no dependa de lo que tarde cada cuadro.
+#[derive(Debug, Clone)]
+pub struct FixedTimestep {
+    pub step: f32,
+    // Tope de pasos por cuadro; lo que sobra se descarta para no acumular
+    // retraso cuando el render es muy lento.
+    pub max_steps: u32,
+    accumulator: f32,
+}
+
+impl FixedTimestep {
+    pub fn new(step: f32) -> Self {
+        FixedTimestep { step, max_steps: 30, accumulator: 0.0 }
+    }
+
+    /// Añade `dt` segundos y devuelve cuántos pasos fijos hay que simular.
+    pub fn advance(&mut self, dt: f32) -> u32 {
+        self.accumulator += dt.max(0.0);
+        let mut steps = 0;
+        while self.accumulator >= self.step && steps < self.max_steps {
+            self.accumulator -= self.step;
+            steps += 1;
+        }
+        if steps == self.max_steps {
+            self.accumulator = self.accumulator.min(self.step);
+        }
+        steps
+    }
+}
+
+/// Espera lo que falte para cumplir la duración objetivo de un cuadro; si el
+/// cuadro ya va tarde no duerme.
+#[derive(Debug, Clone)]
+pub struct FramePacer {
+    pub target: Duration,
+    frame_start: Instant,
+}
+
+impl FramePacer {
+    pub fn new(target: Duration) -> Self {
+        FramePacer { target, frame_start: Instant::now() }
+    }
+
+    pub fn begin_frame(&mut self) {
+        self.frame_start = Instant::now();
+    }
+
+    pub fn end_frame(&self) {
+        let elapsed = self.frame_start.elapsed();
+        if let Some(remaining) = self.target.checked_sub(elapsed) {
+            std::thread::sleep(remaining);
+        }
+    }
+}
+
+/// Duración de los últimos cuadros, para mostrar FPS y tiempo por cuadro.
+#[derive(Debug, Clone)]
+pub struct FrameStats {
+    samples: VecDeque<f32>,
+    capacity: usize,
+}
+
+impl FrameStats {
+    pub fn new(capacity: usize) -> Self {
+        FrameStats { samples: VecDeque::with_capacity(capacity), capacity: capacity.max(1) }
+    }
+
+    pub fn record(&mut self, frame_time: f32) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(frame_time);
+    }
+
+    /// Media en milisegundos.
+    pub fn average_ms(&self) -> f32 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+        self.samples.iter().sum::<f32>() / self.samples.len() as f32 * 1000.0
+    }
+
+    pub fn max_ms(&self) -> f32 {
+        self.samples.iter().copied().fold(0.0, f32::max) * 1000.0
+    }
+
+    pub fn fps(&self) -> f32 {
+        let average = self.average_ms();
+        if average > 0.0 { 1000.0 / average } else { 0.0 }
+    }
+}
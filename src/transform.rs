@@ -0,0 +1,230 @@
+use std::ops::Mul;
+use std::rc::Rc;
+use nalgebra_glm::{self as glm, Mat3, Mat4, Vec3, Vec4};
+
+use crate::geometry::Aabb;
+use crate::material::MaterialOverride;
+use crate::ray_intersect::{Boundary, Intersect, RayIntersect, Span};
+
+/// Matriz 4x4 afín junto con su inversa y la matriz de normales.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub matrix: Mat4,
+    pub inverse: Mat4,
+    normal_matrix: Mat3,
+}
+
+impl Transform {
+    pub fn identity() -> Self {
+        Transform::from_matrix(Mat4::identity())
+    }
+
+    /// Devuelve `None` si la matriz no es invertible (p. ej. escala 0).
+    pub fn try_from_matrix(matrix: Mat4) -> Option<Self> {
+        let inverse = matrix.try_inverse()?;
+        Some(Transform {
+            matrix,
+            inverse,
+            normal_matrix: glm::mat4_to_mat3(&inverse).transpose(),
+        })
+    }
+
+    pub fn from_matrix(matrix: Mat4) -> Self {
+        Transform::try_from_matrix(matrix).expect("Transform matrix is not invertible")
+    }
+
+    pub fn translation(offset: Vec3) -> Self {
+        Transform::from_matrix(glm::translation(&offset))
+    }
+
+    /// Rotación de `angle` radianes alrededor de `axis`.
+    pub fn rotation(angle: f32, axis: Vec3) -> Self {
+        Transform::from_matrix(glm::rotation(angle, &axis.normalize()))
+    }
+
+    pub fn scaling(factors: Vec3) -> Self {
+        Transform::from_matrix(glm::scaling(&factors))
+    }
+
+    /// Traslación, rotación en Y y escala uniforme, en ese orden de aplicación inverso
+    /// (primero escala, luego rota, luego traslada).
+    pub fn from_trs(translation: Vec3, rotation_y: f32, scale: f32) -> Self {
+        Transform::translation(translation)
+            * Transform::rotation(rotation_y, Vec3::new(0.0, 1.0, 0.0))
+            * Transform::scaling(Vec3::repeat(scale))
+    }
+
+    pub fn transform_point(&self, point: &Vec3) -> Vec3 {
+        (self.matrix * Vec4::new(point.x, point.y, point.z, 1.0)).xyz()
+    }
+
+    pub fn transform_normal(&self, normal: &Vec3) -> Vec3 {
+        (self.normal_matrix * normal).normalize()
+    }
+
+    pub fn inverse_transform_point(&self, point: &Vec3) -> Vec3 {
+        (self.inverse * Vec4::new(point.x, point.y, point.z, 1.0)).xyz()
+    }
+
+    pub fn inverse_transform_vector(&self, vector: &Vec3) -> Vec3 {
+        (self.inverse * Vec4::new(vector.x, vector.y, vector.z, 0.0)).xyz()
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::identity()
+    }
+}
+
+impl Mul for Transform {
+    type Output = Transform;
+
+    // `a * b` aplica primero `b` y luego `a`.
+    fn mul(self, other: Transform) -> Transform {
+        Transform {
+            matrix: self.matrix * other.matrix,
+            inverse: other.inverse * self.inverse,
+            normal_matrix: self.normal_matrix * other.normal_matrix,
+        }
+    }
+}
+
+/// Cualquier primitiva colocada con una transformación. Como el objeto va en
+/// un `Rc`, la misma definición puede instanciarse muchas veces, cada una
+/// con su propio ajuste de material si hace falta.
+#[derive(Clone)]
+pub struct Transformed {
+    pub object: Rc<dyn RayIntersect>,
+    pub transform: Transform,
+    pub material: Option<MaterialOverride>,
+}
+
+impl Transformed {
+    pub fn new(object: Rc<dyn RayIntersect>, transform: Transform) -> Self {
+        Transformed { object, transform, material: None }
+    }
+
+    pub fn with_material(mut self, material: MaterialOverride) -> Self {
+        self.material = Some(material);
+        self
+    }
+
+    // Lleva un impacto calculado en espacio del objeto al del mundo.
+    fn to_world(&self, ray_origin: &Vec3, ray_direction: &Vec3, mut intersect: Intersect) -> Intersect {
+        intersect.point = ray_origin + ray_direction * intersect.distance;
+        intersect.normal = self.transform.transform_normal(&intersect.normal);
+        if let Some(material) = &self.material {
+            material.apply(&mut intersect.material);
+        }
+        intersect
+    }
+}
+
+impl RayIntersect for Transformed {
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
+        // La dirección local no se normaliza: así la distancia t coincide en
+        // ambos espacios.
+        let local_origin = self.transform.inverse_transform_point(ray_origin);
+        let local_direction = self.transform.inverse_transform_vector(ray_direction);
+
+        let intersect = self.object.ray_intersect(&local_origin, &local_direction);
+        if !intersect.is_intersecting {
+            return intersect;
+        }
+        self.to_world(ray_origin, ray_direction, intersect)
+    }
+
+    fn spans(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<Vec<Span>> {
+        let local_origin = self.transform.inverse_transform_point(ray_origin);
+        let local_direction = self.transform.inverse_transform_vector(ray_direction);
+
+        let spans = self.object.spans(&local_origin, &local_direction)?;
+        let to_world = |b: Boundary| Boundary { distance: b.distance, normal: self.transform.transform_normal(&b.normal) };
+        let spans = spans
+            .into_iter()
+            .map(|span| Span { enter: to_world(span.enter), exit: to_world(span.exit) })
+            .collect();
+        Some(spans)
+    }
+
+    fn surface_at(&self, ray_origin: &Vec3, ray_direction: &Vec3, distance: f32) -> Option<Intersect> {
+        let local_origin = self.transform.inverse_transform_point(ray_origin);
+        let local_direction = self.transform.inverse_transform_vector(ray_direction);
+
+        let intersect = self.object.surface_at(&local_origin, &local_direction, distance)?;
+        Some(self.to_world(ray_origin, ray_direction, intersect))
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        self.object.bounds().map(|b| b.transformed(&self.transform))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::Cube;
+    use crate::texture::Texture;
+
+    fn cube(min: Vec3, max: Vec3) -> Cube {
+        let texture = Rc::new(Texture::new("textures/snow.png"));
+        Cube::try_new(min, max, texture.clone(), texture.clone(), texture).unwrap()
+    }
+
+    fn assert_close(a: &[f32], b: &[f32]) {
+        assert!(a.iter().zip(b).all(|(x, y)| (x - y).abs() < 1e-4), "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn composition_keeps_inverse_and_normal_matrix() {
+        let a = Transform::translation(Vec3::new(1.0, -2.0, 3.0)) * Transform::rotation(0.7, Vec3::new(1.0, 1.0, 0.0));
+        let b = Transform::scaling(Vec3::new(2.0, 0.5, 3.0)) * Transform::rotation(-1.2, Vec3::new(0.0, 0.0, 1.0));
+        let composed = a * b;
+        let direct = Transform::from_matrix(a.matrix * b.matrix);
+
+        assert_close(composed.matrix.as_slice(), direct.matrix.as_slice());
+        assert_close(composed.inverse.as_slice(), direct.inverse.as_slice());
+        assert_close(composed.normal_matrix.as_slice(), direct.normal_matrix.as_slice());
+        assert_close((composed.matrix * composed.inverse).as_slice(), Mat4::identity().as_slice());
+    }
+
+    #[test]
+    fn normals_stay_perpendicular_under_non_uniform_scale() {
+        let transform = Transform::rotation(0.4, Vec3::new(0.0, 1.0, 0.0)) * Transform::scaling(Vec3::new(4.0, 1.0, 0.25));
+        // Plano x + y + z = 0, con dos direcciones contenidas en él
+        let normal = Vec3::new(1.0, 1.0, 1.0).normalize();
+        let tangents = [Vec3::new(1.0, -1.0, 0.0), Vec3::new(0.0, 1.0, -1.0)];
+
+        let world_normal = transform.transform_normal(&normal);
+        assert!((world_normal.norm() - 1.0).abs() < 1e-5);
+        for tangent in tangents {
+            let world_tangent = transform.transform_point(&tangent) - transform.transform_point(&Vec3::zeros());
+            assert!(world_normal.dot(&world_tangent).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn transformed_hits_match_the_equivalent_cube() {
+        let unit = Rc::new(cube(Vec3::zeros(), Vec3::new(1.0, 1.0, 1.0)));
+        let placed = Transformed::new(
+            unit,
+            Transform::translation(Vec3::new(2.0, 0.0, -1.0)) * Transform::scaling(Vec3::new(2.0, 3.0, 0.5)),
+        );
+        let reference = cube(Vec3::new(2.0, 0.0, -1.0), Vec3::new(4.0, 3.0, -0.5));
+
+        let rays = [
+            (Vec3::new(3.0, 10.0, -0.75), Vec3::new(0.0, -1.0, 0.0)),
+            (Vec3::new(-5.0, 1.0, -0.8), Vec3::new(1.0, 0.0, 0.0)),
+            (Vec3::new(0.0, 6.0, 2.0), Vec3::new(0.5, -0.8, -0.45).normalize()),
+        ];
+        for (origin, direction) in rays {
+            let expected = reference.ray_intersect(&origin, &direction);
+            let hit = placed.ray_intersect(&origin, &direction);
+            assert!(expected.is_intersecting && hit.is_intersecting);
+            assert!((hit.distance - expected.distance).abs() < 1e-4);
+            assert_close(hit.point.as_slice(), expected.point.as_slice());
+            assert_close(hit.normal.as_slice(), expected.normal.as_slice());
+        }
+    }
+}
@@ -0,0 +1,373 @@
+use std::rc::Rc;
+use nalgebra_glm::Vec3;
+
+use crate::geometry::Aabb;
+use crate::material::Material;
+use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::texture::Texture;
+
+pub const CHUNK_SIZE: usize = 16;
+const CHUNK_VOLUME: usize = CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE;
+
+/// Índice en la paleta de bloques; 0 es aire.
+pub type BlockId = u16;
+pub const AIR: BlockId = 0;
+
+/// Tipo de bloque con texturas por cara, como las de `Cube`.
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub top_texture: Rc<Texture>,
+    pub bottom_texture: Rc<Texture>,
+    pub side_texture: Rc<Texture>,
+}
+
+impl Block {
+    pub fn new(top_texture: Rc<Texture>, bottom_texture: Rc<Texture>, side_texture: Rc<Texture>) -> Self {
+        Block { top_texture, bottom_texture, side_texture }
+    }
+
+    pub fn uniform(texture: Rc<Texture>) -> Self {
+        Block::new(texture.clone(), texture.clone(), texture)
+    }
+
+    fn texture(&self, normal: &Vec3) -> &Rc<Texture> {
+        if normal.y > 0.5 {
+            &self.top_texture
+        } else if normal.y < -0.5 {
+            &self.bottom_texture
+        } else {
+            &self.side_texture
+        }
+    }
+}
+
+// Bloques de 16x16x16 celdas; los trozos vacíos no se guardan.
+#[derive(Clone)]
+struct Chunk {
+    blocks: Box<[BlockId; CHUNK_VOLUME]>,
+    solid: usize,
+}
+
+impl Chunk {
+    fn new() -> Self {
+        Chunk { blocks: Box::new([AIR; CHUNK_VOLUME]), solid: 0 }
+    }
+
+    fn index(x: usize, y: usize, z: usize) -> usize {
+        (z * CHUNK_SIZE + y) * CHUNK_SIZE + x
+    }
+}
+
+/// Rejilla de bloques dividida en trozos, recorrida con DDA en dos niveles:
+/// primero por trozos, saltando los vacíos, y luego por celdas.
+#[derive(Clone)]
+pub struct VoxelGrid {
+    pub origin: Vec3,
+    pub voxel_size: f32,
+    // Tamaño en trozos
+    chunk_counts: [usize; 3],
+    chunks: Vec<Option<Chunk>>,
+    palette: Vec<Block>,
+}
+
+impl VoxelGrid {
+    /// `size` en bloques; se redondea hacia arriba a trozos completos.
+    pub fn new(origin: Vec3, voxel_size: f32, size: [usize; 3]) -> Self {
+        let chunk_counts = size.map(|s| s.div_ceil(CHUNK_SIZE).max(1));
+        VoxelGrid {
+            origin,
+            voxel_size,
+            chunk_counts,
+            chunks: vec![None; chunk_counts[0] * chunk_counts[1] * chunk_counts[2]],
+            palette: Vec::new(),
+        }
+    }
+
+    /// Registra un tipo de bloque y devuelve su identificador.
+    pub fn add_block(&mut self, block: Block) -> BlockId {
+        self.palette.push(block);
+        self.palette.len() as BlockId
+    }
+
+    pub fn size(&self) -> [usize; 3] {
+        self.chunk_counts.map(|c| c * CHUNK_SIZE)
+    }
+
+    fn chunk_index(&self, cx: usize, cy: usize, cz: usize) -> usize {
+        (cz * self.chunk_counts[1] + cy) * self.chunk_counts[0] + cx
+    }
+
+    pub fn get(&self, x: usize, y: usize, z: usize) -> BlockId {
+        let size = self.size();
+        if x >= size[0] || y >= size[1] || z >= size[2] {
+            return AIR;
+        }
+        let index = self.chunk_index(x / CHUNK_SIZE, y / CHUNK_SIZE, z / CHUNK_SIZE);
+        match &self.chunks[index] {
+            Some(chunk) => chunk.blocks[Chunk::index(x % CHUNK_SIZE, y % CHUNK_SIZE, z % CHUNK_SIZE)],
+            None => AIR,
+        }
+    }
+
+    /// Coloca un bloque; fuera de la rejilla o con un id desconocido no hace nada.
+    pub fn set(&mut self, x: usize, y: usize, z: usize, block: BlockId) {
+        let size = self.size();
+        if x >= size[0] || y >= size[1] || z >= size[2] || block as usize > self.palette.len() {
+            return;
+        }
+        let index = self.chunk_index(x / CHUNK_SIZE, y / CHUNK_SIZE, z / CHUNK_SIZE);
+        let chunk = self.chunks[index].get_or_insert_with(Chunk::new);
+        let cell = &mut chunk.blocks[Chunk::index(x % CHUNK_SIZE, y % CHUNK_SIZE, z % CHUNK_SIZE)];
+        match (*cell == AIR, block == AIR) {
+            (true, false) => chunk.solid += 1,
+            (false, true) => chunk.solid -= 1,
+            _ => {}
+        }
+        *cell = block;
+        if chunk.solid == 0 {
+            self.chunks[index] = None;
+        }
+    }
+
+    /// Columnas de bloques a partir de una función de altura (en bloques);
+    /// `layer` elige el bloque según la altura y la profundidad bajo la superficie.
+    pub fn fill_heightmap<H, L>(&mut self, height: H, layer: L)
+    where
+        H: Fn(usize, usize) -> usize,
+        L: Fn(usize, usize) -> BlockId,
+    {
+        let size = self.size();
+        for z in 0..size[2] {
+            for x in 0..size[0] {
+                let top = height(x, z).min(size[1]);
+                for y in 0..top {
+                    self.set(x, y, z, layer(y, top - 1 - y));
+                }
+            }
+        }
+    }
+
+    fn chunk_is_empty(&self, cell: [i32; 3]) -> bool {
+        self.chunks[self.chunk_index(cell[0] as usize, cell[1] as usize, cell[2] as usize)].is_none()
+    }
+
+    fn hit(&self, ray_origin: &Vec3, ray_direction: &Vec3, distance: f32, block: BlockId, normal: Vec3) -> Intersect {
+        let point = ray_origin + ray_direction * distance;
+        let local = (point - self.origin) / self.voxel_size;
+        let frac = |v: f32| v - v.floor();
+
+        // Mismas UV por cara que `Cube`, repetidas en cada bloque
+        let (u, v) = if normal.y.abs() > 0.5 {
+            (frac(local.x), frac(local.z))
+        } else if normal.x.abs() > 0.5 {
+            (frac(local.z), frac(local.y))
+        } else {
+            (frac(local.x), frac(local.y))
+        };
+
+        let block = &self.palette[block as usize - 1];
+        let material = Material::new(
+            [0.9, 0.1, 0.0, 0.0],
+            [255, 255, 255],
+            10.0,
+            1.0,
+            Some(block.texture(&normal).clone()),
+        );
+        let mut intersect = Intersect::new(point, normal, distance, material, Some(u), Some(1.0 - v));
+        intersect.local_point = point - self.origin;
+        intersect
+    }
+}
+
+// DDA 3D sobre celdas unitarias en `[lo, hi)`, con t dentro de `range`.
+// `visit` recibe la celda, su intervalo de t y el eje por el que se entró
+// (`None` si el rayo empieza dentro de ella); devuelve si hay que seguir.
+fn traverse<F>(origin: &Vec3, direction: &Vec3, lo: [i32; 3], hi: [i32; 3], range: (f32, f32), entry_axis: Option<usize>, mut visit: F)
+where
+    F: FnMut([i32; 3], f32, f32, Option<usize>) -> bool,
+{
+    let (t_start, t_end) = range;
+    // Un poco hacia dentro para no caer en la celda anterior por redondeo
+    let start = origin + direction * (t_start + 1e-5);
+    let mut cell = [0i32; 3];
+    let mut step = [0i32; 3];
+    let mut t_max = [f32::INFINITY; 3];
+    let mut t_delta = [f32::INFINITY; 3];
+    for axis in 0..3 {
+        cell[axis] = (start[axis].floor() as i32).clamp(lo[axis], hi[axis] - 1);
+        if direction[axis] > 0.0 {
+            step[axis] = 1;
+            t_max[axis] = ((cell[axis] + 1) as f32 - origin[axis]) / direction[axis];
+            t_delta[axis] = 1.0 / direction[axis];
+        } else if direction[axis] < 0.0 {
+            step[axis] = -1;
+            t_max[axis] = (cell[axis] as f32 - origin[axis]) / direction[axis];
+            t_delta[axis] = -1.0 / direction[axis];
+        }
+    }
+
+    let mut t_in = t_start;
+    let mut axis_in = entry_axis;
+    loop {
+        let axis = if t_max[0] < t_max[1] {
+            if t_max[0] < t_max[2] { 0 } else { 2 }
+        } else if t_max[1] < t_max[2] {
+            1
+        } else {
+            2
+        };
+        let t_out = t_max[axis].min(t_end);
+        if !visit(cell, t_in, t_out, axis_in) || t_max[axis] >= t_end {
+            return;
+        }
+
+        cell[axis] += step[axis];
+        if cell[axis] < lo[axis] || cell[axis] >= hi[axis] {
+            return;
+        }
+        t_in = t_max[axis];
+        t_max[axis] += t_delta[axis];
+        axis_in = Some(axis);
+    }
+}
+
+impl RayIntersect for VoxelGrid {
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
+        // En espacio de bloques t no cambia: origen y dirección se escalan igual
+        let origin = (ray_origin - self.origin) / self.voxel_size;
+        let direction = ray_direction / self.voxel_size;
+        let size = self.size().map(|s| s as f32);
+
+        let mut t_enter: f32 = 0.0;
+        let mut t_leave = f32::INFINITY;
+        let mut entry_axis = None;
+        for axis in 0..3 {
+            let inv = 1.0 / direction[axis];
+            let mut t0 = -origin[axis] * inv;
+            let mut t1 = (size[axis] - origin[axis]) * inv;
+            if t0 > t1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            // Rayo paralelo dentro de la losa: 0 * inf da NaN
+            if t0.is_nan() || t1.is_nan() {
+                continue;
+            }
+            if t0 > t_enter {
+                t_enter = t0;
+                entry_axis = Some(axis);
+            }
+            t_leave = t_leave.min(t1);
+        }
+        if t_enter > t_leave {
+            return Intersect::empty();
+        }
+
+        let chunk_scale = CHUNK_SIZE as f32;
+        let chunk_origin = origin / chunk_scale;
+        let chunk_direction = direction / chunk_scale;
+        let chunk_hi = self.chunk_counts.map(|c| c as i32);
+        let mut result = None;
+
+        traverse(&chunk_origin, &chunk_direction, [0; 3], chunk_hi, (t_enter, t_leave), entry_axis, |chunk, t0, t1, chunk_axis| {
+            if self.chunk_is_empty(chunk) {
+                return true;
+            }
+            let lo = chunk.map(|c| c * CHUNK_SIZE as i32);
+            let hi = lo.map(|c| c + CHUNK_SIZE as i32);
+            traverse(&origin, &direction, lo, hi, (t0, t1), chunk_axis, |cell, t_in, _, axis| {
+                let block = self.get(cell[0] as usize, cell[1] as usize, cell[2] as usize);
+                if block == AIR {
+                    return true;
+                }
+                // Si el rayo nace dentro de un bloque se ignora ese bloque
+                let Some(axis) = axis else {
+                    return true;
+                };
+                let mut normal = Vec3::zeros();
+                normal[axis] = -direction[axis].signum();
+                result = Some((t_in, block, normal));
+                false
+            });
+            result.is_none()
+        });
+
+        match result {
+            Some((distance, block, normal)) => self.hit(ray_origin, ray_direction, distance, block, normal),
+            None => Intersect::empty(),
+        }
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        let size = self.size().map(|s| s as f32);
+        Some(Aabb::new(self.origin, self.origin + Vec3::new(size[0], size[1], size[2]) * self.voxel_size))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Rejilla de 3x1x1 trozos con bloques de una unidad en las celdas dadas.
+    fn grid(cells: &[[usize; 3]]) -> VoxelGrid {
+        let mut grid = VoxelGrid::new(Vec3::zeros(), 1.0, [3 * CHUNK_SIZE, CHUNK_SIZE, CHUNK_SIZE]);
+        let block = grid.add_block(Block::uniform(Rc::new(Texture::new("textures/snow.png"))));
+        for &[x, y, z] in cells {
+            grid.set(x, y, z, block);
+        }
+        grid
+    }
+
+    fn assert_hit(hit: &Intersect, distance: f32, normal: Vec3) {
+        assert!(hit.is_intersecting);
+        assert!((hit.distance - distance).abs() < 1e-4, "distance {}", hit.distance);
+        assert_eq!(hit.normal, normal);
+    }
+
+    #[test]
+    fn axis_aligned_rays_hit_the_facing_side() {
+        let grid = grid(&[[5, 2, 3]]);
+        let from_side = grid.ray_intersect(&Vec3::new(-2.0, 2.5, 3.5), &Vec3::new(1.0, 0.0, 0.0));
+        assert_hit(&from_side, 7.0, Vec3::new(-1.0, 0.0, 0.0));
+        assert_eq!(from_side.point, Vec3::new(5.0, 2.5, 3.5));
+
+        let from_above = grid.ray_intersect(&Vec3::new(5.5, 20.0, 3.5), &Vec3::new(0.0, -1.0, 0.0));
+        assert_hit(&from_above, 17.0, Vec3::new(0.0, 1.0, 0.0));
+
+        let past = grid.ray_intersect(&Vec3::new(-2.0, 2.5, 4.5), &Vec3::new(1.0, 0.0, 0.0));
+        assert!(!past.is_intersecting);
+    }
+
+    #[test]
+    fn rays_cross_empty_chunks() {
+        let grid = grid(&[[40, 7, 7]]);
+        assert!(grid.chunk_is_empty([0, 0, 0]) && grid.chunk_is_empty([1, 0, 0]));
+        assert!(!grid.chunk_is_empty([2, 0, 0]));
+
+        let hit = grid.ray_intersect(&Vec3::new(-1.0, 7.5, 7.5), &Vec3::new(1.0, 0.0, 0.0));
+        assert_hit(&hit, 41.0, Vec3::new(-1.0, 0.0, 0.0));
+        let diagonal = Vec3::new(1.0, 0.1, 0.0).normalize();
+        let hit = grid.ray_intersect(&Vec3::new(-1.0, 3.5, 7.5), &diagonal);
+        assert!(hit.is_intersecting);
+        assert!((hit.point.x - 40.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn rays_starting_inside_a_block_skip_it() {
+        let grid = grid(&[[5, 2, 3], [8, 2, 3]]);
+        let hit = grid.ray_intersect(&Vec3::new(5.5, 2.5, 3.5), &Vec3::new(1.0, 0.0, 0.0));
+        assert_hit(&hit, 2.5, Vec3::new(-1.0, 0.0, 0.0));
+
+        let alone = grid.ray_intersect(&Vec3::new(8.5, 2.5, 3.5), &Vec3::new(1.0, 0.0, 0.0));
+        assert!(!alone.is_intersecting);
+    }
+
+    #[test]
+    fn rays_along_the_grid_boundary() {
+        // El origen está justo en el plano y = 0 del borde de la rejilla
+        let grid = grid(&[[6, 0, 0]]);
+        let hit = grid.ray_intersect(&Vec3::new(-3.0, 0.0, 0.5), &Vec3::new(1.0, 0.0, 0.0));
+        assert_hit(&hit, 9.0, Vec3::new(-1.0, 0.0, 0.0));
+        let outside = grid.ray_intersect(&Vec3::new(-3.0, -0.5, 0.5), &Vec3::new(1.0, 0.0, 0.0));
+        assert!(!outside.is_intersecting);
+    }
+}